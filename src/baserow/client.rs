@@ -0,0 +1,323 @@
+use crate::baserow::config::BaserowConfig;
+use crate::baserow::structs::{Field, Page, Row};
+use reqwest::header::{AUTHORIZATION, CONTENT_TYPE};
+use reqwest::{RequestBuilder, StatusCode, Url};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use snafu::{OptionExt, ResultExt, Snafu};
+
+const LIST_PAGE_SIZE: usize = 200;
+
+#[derive(Snafu, Debug)]
+#[snafu(visibility(pub(crate)))]
+pub enum Error {
+    #[snafu(display("Failed to parse Baserow url [{url}]: {source}"))]
+    ParseUrl {
+        source: oauth2::url::ParseError,
+        url: String,
+    },
+    #[snafu(display("Failed to {msg}:  {source}"))]
+    Reqwest { source: reqwest::Error, msg: String },
+    #[snafu(display("Failed to {msg} at [{url}]: {source}"))]
+    ReqwestWithUrl {
+        source: reqwest::Error,
+        msg: String,
+        url: String,
+    },
+    #[snafu(display("Failed to {msg}, [{url}] responded with {status}: {body}"))]
+    Status {
+        msg: String,
+        url: String,
+        status: StatusCode,
+        body: String,
+    },
+    #[snafu(display("Failed to parse response from [{url}]: {source} - response was: {body}"))]
+    DecodeJson {
+        source: serde_json::Error,
+        url: String,
+        body: String,
+    },
+    #[snafu(display("Failed to serialize row: {source}"))]
+    SerializeRow { source: serde_json::Error },
+    #[snafu(display("Field [{field}] has no option [{value}]"))]
+    UnknownSelectOption { field: String, value: String },
+}
+
+/// Reads and writes rows of Baserow tables via the REST API.
+///
+/// Rows are read and written with field keys such as `field_4565570` rather than field names,
+/// so that renaming a field in Baserow doesn't break the types generated by
+/// [`crate::baserow::codegen`].
+pub struct Baserow {
+    config: BaserowConfig,
+    client: reqwest::Client,
+}
+
+impl Baserow {
+    pub fn new(config: BaserowConfig) -> Result<Self, Error> {
+        let client = reqwest::ClientBuilder::new()
+            // Following redirects opens the client up to SSRF vulnerabilities.
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .context(ReqwestSnafu {
+                msg: "build client",
+            })?;
+        Ok(Self { config, client })
+    }
+
+    /// Lists the fields of the table with the ID `table_id`.
+    pub async fn list_fields(&self, table_id: usize) -> Result<Vec<Field>, Error> {
+        let url = self
+            .config
+            .endpoint(&format!("database/fields/table/{table_id}/"))?;
+        self.send_json(self.client.get(url.as_ref()), "list fields", &url)
+            .await
+    }
+
+    /// Retrieves all rows of the table with the ID `table_id`.
+    pub async fn list_rows<T>(&self, table_id: usize) -> Result<Vec<Row<T>>, Error>
+    where
+        T: DeserializeOwned,
+    {
+        let url = self
+            .config
+            .endpoint(&format!("database/rows/table/{table_id}/"))?;
+        let mut rows = Vec::new();
+
+        for page in 1.. {
+            let request = self.client.get(url.as_ref()).query(&[
+                ("size", LIST_PAGE_SIZE.to_string()),
+                ("page", page.to_string()),
+            ]);
+            let page: Page<Row<T>> = self.send_json(request, "list rows", &url).await?;
+            rows.extend(page.results);
+            if page.next.is_none() {
+                break;
+            }
+        }
+        Ok(rows)
+    }
+
+    /// Adds `row` to the table with the ID `table_id`, see [`row_body`] for how `fields` is
+    /// used.
+    pub async fn create_row<T>(
+        &self,
+        table_id: usize,
+        fields: &[Field],
+        row: &T,
+    ) -> Result<Row<T>, Error>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let url = self
+            .config
+            .endpoint(&format!("database/rows/table/{table_id}/"))?;
+        let request = self.client.post(url.as_ref()).json(&row_body(fields, row)?);
+        self.send_json(request, "create row", &url).await
+    }
+
+    /// Overwrites the row with the ID `row_id` with `row`, see [`row_body`] for how `fields`
+    /// is used.
+    pub async fn update_row<T>(
+        &self,
+        table_id: usize,
+        row_id: usize,
+        fields: &[Field],
+        row: &T,
+    ) -> Result<Row<T>, Error>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let url = self
+            .config
+            .endpoint(&format!("database/rows/table/{table_id}/{row_id}/"))?;
+        let request = self
+            .client
+            .patch(url.as_ref())
+            .json(&row_body(fields, row)?);
+        self.send_json(request, "update row", &url).await
+    }
+
+    async fn send_json<T>(&self, request: RequestBuilder, msg: &str, url: &Url) -> Result<T, Error>
+    where
+        T: DeserializeOwned,
+    {
+        let response = request
+            .header(AUTHORIZATION, format!("Token {}", self.config.token))
+            .header(CONTENT_TYPE, "application/json")
+            .send()
+            .await
+            .context(ReqwestWithUrlSnafu {
+                msg,
+                url: url.as_ref(),
+            })?;
+        let status = response.status();
+        let body = response.text().await.context(ReqwestWithUrlSnafu {
+            msg: format!("read response to {msg}"),
+            url: url.as_ref(),
+        })?;
+        if !status.is_success() {
+            return StatusSnafu {
+                msg,
+                url: url.as_ref(),
+                status,
+                body,
+            }
+            .fail();
+        }
+
+        serde_json::from_str(&body).context(DecodeJsonSnafu {
+            url: url.as_ref(),
+            body,
+        })
+    }
+}
+
+/// Renders `row` as it is sent to Baserow.
+///
+/// Single select values are read as objects with the `value`, `id` and `color` of the option,
+/// but have to be written as the ID of the option. They are therefore looked up by value in
+/// the options of their field in `fields`, so that the option can be picked by value alone.
+pub fn row_body<T>(fields: &[Field], row: &T) -> Result<Value, Error>
+where
+    T: Serialize,
+{
+    let mut body = serde_json::to_value(row).context(SerializeRowSnafu)?;
+    let Some(values) = body.as_object_mut() else {
+        return Ok(body);
+    };
+
+    for (key, value) in values.iter_mut() {
+        let Some(selected) = value.get("value").and_then(Value::as_str) else {
+            continue;
+        };
+        let option = fields
+            .iter()
+            .find(|field| field.key() == *key)
+            .and_then(|field| field.select_options.as_ref())
+            .and_then(|options| options.iter().find(|option| option.value == selected))
+            .context(UnknownSelectOptionSnafu {
+                field: key.as_str(),
+                value: selected,
+            })?;
+        *value = Value::from(option.id);
+    }
+    Ok(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::baserow::client::{row_body, Baserow, Error};
+    use crate::baserow::config::BaserowConfig;
+    use crate::baserow::structs::Field;
+    use crate::generated::customersuccess::Offers;
+    use serde_json::json;
+    use std::fs::read_to_string;
+    use std::str::FromStr;
+    use wiremock::matchers::{body_json, header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn offer_fields() -> Vec<Field> {
+        serde_json::from_str(&read_to_string("testdata/baserow/offers_fields.json").unwrap())
+            .unwrap()
+    }
+
+    fn client_for(server: &MockServer) -> Baserow {
+        Baserow::new(BaserowConfig::new(&server.uri(), "database-token").unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_list_rows() {
+        let server = MockServer::start().await;
+        let mut page1: serde_json::Value =
+            serde_json::from_str(&read_to_string("testdata/list_offers1.json").unwrap()).unwrap();
+        let page2 = json!({
+            "count": 4,
+            "next": null,
+            "previous": null,
+            "results": [page1["results"][2].clone()],
+        });
+        page1["next"] = json!(format!(
+            "{}/api/database/rows/table/568215/?page=2",
+            server.uri()
+        ));
+        for (page, body) in [("1", page1), ("2", page2)] {
+            Mock::given(method("GET"))
+                .and(path("/api/database/rows/table/568215/"))
+                .and(header("Authorization", "Token database-token"))
+                .and(query_param("page", page))
+                .respond_with(ResponseTemplate::new(200).set_body_json(body))
+                .expect(1)
+                .mount(&server)
+                .await;
+        }
+
+        let rows = client_for(&server)
+            .list_rows::<Offers>(568215)
+            .await
+            .unwrap();
+
+        assert_eq!(rows.len(), 4);
+        assert_eq!(rows[2].id, 34);
+        assert_eq!(rows[2].fields.easybill_id, Some(123));
+        assert_eq!(rows[2].fields.amount, Some(12000.0));
+        assert_eq!(rows[2].fields.status.as_ref().unwrap().to_string(), "sent");
+        assert_eq!(rows[0].fields.easybill_id, None);
+    }
+
+    #[tokio::test]
+    async fn test_update_row() {
+        let server = MockServer::start().await;
+        Mock::given(method("PATCH"))
+            .and(path("/api/database/rows/table/568215/34/"))
+            .and(body_json(json!({
+                "field_4565570": 123,
+                "field_4565571": "Company A",
+                "field_4565572": 12500.0,
+                "field_4565632": 3487586
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "id": 34,
+                "field_4565570": "123",
+                "field_4565571": "Company A",
+                "field_4565572": "12500.00",
+                "field_4565632": { "id": 3487586, "value": "accepted", "color": "green" }
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let offer = Offers {
+            easybill_id: Some(123),
+            customer: Some("Company A".to_string()),
+            amount: Some(12500.0),
+            status: Some(FromStr::from_str("accepted").unwrap()),
+        };
+
+        let row = client_for(&server)
+            .update_row(568215, 34, &offer_fields(), &offer)
+            .await
+            .unwrap();
+
+        assert_eq!(row.fields.amount, Some(12500.0));
+    }
+
+    #[test]
+    fn test_row_body_unknown_option() {
+        let offer = Offers {
+            easybill_id: Some(123),
+            customer: None,
+            amount: None,
+            status: Some(FromStr::from_str("accepted").unwrap()),
+        };
+        let mut fields = offer_fields();
+        fields.retain(|field| field.name != "status");
+
+        let error = row_body(&fields, &offer).unwrap_err();
+
+        assert!(matches!(
+            error,
+            Error::UnknownSelectOption { field, value } if field == "field_4565632" && value == "accepted"
+        ));
+    }
+}
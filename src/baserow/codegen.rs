@@ -0,0 +1,362 @@
+//! Generates Rust types for Baserow tables, like the ones in [`crate::generated`].
+//!
+//! Every table becomes a struct with one optional member per field, renamed to the field key
+//! (`field_4565570`) so that renaming a field in Baserow doesn't break reading rows. Single
+//! select fields with options become an enum per field, and the primary field is used as the
+//! identifier of [`BaserowObject`](baserow_client::client::BaserowObject).
+
+use crate::baserow::structs::{Field, SelectOption};
+use convert_case::{Boundary, Case, Casing};
+use quote::format_ident;
+use snafu::{OptionExt, Snafu};
+use std::collections::HashSet;
+use syn::{parse_quote, Item, Type};
+
+const HEADER: &str =
+    "// Generated by `serious-business baserow codegen`, changes will be overwritten.\n\n";
+
+/// Words that can't be used as identifiers, these get an `_` appended.
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate",
+    "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl",
+    "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "return", "self", "Self", "static", "struct", "super", "trait", "true", "try", "type",
+    "typeof", "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+#[derive(Snafu, Debug)]
+pub enum Error {
+    #[snafu(display("Table [{table}] has no primary field"))]
+    NoPrimaryField { table: String },
+}
+
+/// A table to generate types for.
+pub struct Table {
+    /// Name of the generated struct, converted to UpperCamelCase
+    pub name: String,
+    pub id: usize,
+    pub fields: Vec<Field>,
+}
+
+/// Renders the types of all `tables` as the content of one Rust module.
+pub fn generate(tables: &[Table]) -> Result<String, Error> {
+    let mut items: Vec<Item> = vec![
+        parse_quote!(
+            use baserow_client::client::{BaserowObject, Identifier};
+        ),
+        parse_quote!(
+            use serde::{Deserialize, Serialize};
+        ),
+    ];
+    for table in tables {
+        items.extend(table_items(table)?);
+    }
+
+    let file = syn::File {
+        shebang: None,
+        attrs: Vec::new(),
+        items,
+    };
+    Ok(format!("{HEADER}{}", prettyplease::unparse(&file)))
+}
+
+/// The struct of `table`, the enums of its single select fields and its `BaserowObject` impl.
+fn table_items(table: &Table) -> Result<Vec<Item>, Error> {
+    let struct_name = identifier(&table.name, Case::UpperCamel, "Table");
+    let primary = table
+        .fields
+        .iter()
+        .find(|field| field.primary)
+        .context(NoPrimaryFieldSnafu {
+            table: table.name.as_str(),
+        })?;
+
+    let mut items = Vec::new();
+    let mut members = Vec::new();
+    let mut member_names = HashSet::new();
+    let mut primary_member = None;
+    let mut primary_is_number = false;
+
+    for field in &table.fields {
+        let mut name = identifier(&field.name, Case::Snake, "field_");
+        if !member_names.insert(name.clone()) {
+            name = format!("{name}_{}", field.id);
+        }
+        let name = format_ident!("{name}");
+        let key = field.key();
+
+        let (ty, deserialize_with) = match field.select_options.as_deref() {
+            Some(options) if field.field_type == "single_select" && !options.is_empty() => {
+                let enum_name = format_ident!(
+                    "{struct_name}{}",
+                    identifier(&field.name, Case::UpperCamel, "Field")
+                );
+                items.push(select_enum(&enum_name, options));
+                (parse_quote!(#enum_name), None)
+            }
+            _ => field_type(field),
+        };
+
+        if field.id == primary.id {
+            primary_member = Some(name.clone());
+            primary_is_number = deserialize_with == Some(USIZE_OR_NULL);
+        }
+        members.push(match deserialize_with {
+            Some(deserialize_with) => quote::quote! {
+                #[serde(rename = #key, default, deserialize_with = #deserialize_with)]
+                pub #name: Option<#ty>
+            },
+            None => quote::quote! {
+                #[serde(rename = #key)]
+                pub #name: Option<#ty>
+            },
+        });
+    }
+
+    let struct_name = format_ident!("{struct_name}");
+    items.insert(
+        0,
+        parse_quote! {
+            #[derive(Serialize, Deserialize, Debug, Clone)]
+            pub struct #struct_name {
+                #(#members,)*
+            }
+        },
+    );
+
+    let table_id = table.id;
+    let primary_key = primary.key();
+    let primary_member = primary_member.expect("the primary field is one of the fields");
+    let get_id: syn::Expr = if primary_is_number {
+        parse_quote!(Identifier::UnsignedNumber {
+            id: self.#primary_member,
+        })
+    } else {
+        parse_quote!(Identifier::Text {
+            id: Some(match &self.#primary_member {
+                None => "".to_string(),
+                Some(name) => name.to_string(),
+            }),
+        })
+    };
+    items.push(parse_quote! {
+        impl BaserowObject for #struct_name {
+            fn get_static_table_id() -> usize {
+                #table_id
+            }
+            fn get_table_id(&self) -> usize {
+                Self::get_static_table_id()
+            }
+            fn get_id(&self) -> Identifier {
+                #get_id
+            }
+            fn get_table_id_field(&self) -> String {
+                #primary_key.to_string()
+            }
+        }
+    });
+    Ok(items)
+}
+
+const USIZE_OR_NULL: &str = "crate::baserow::de::usize_or_null";
+const FLOAT_OR_NULL: &str = "crate::baserow::de::float_or_null";
+
+/// The type of values of `field`, and the deserializer to read them with if serde's own
+/// doesn't understand what Baserow sends.
+///
+/// Fields whose values have no fixed shape, such as links to other tables or formulas, are
+/// kept as JSON.
+fn field_type(field: &Field) -> (Type, Option<&'static str>) {
+    match field.field_type.as_str() {
+        "text" | "long_text" | "url" | "email" | "phone_number" | "ai" => {
+            (parse_quote!(String), None)
+        }
+        "boolean" => (parse_quote!(bool), None),
+        "number"
+            if field.number_decimal_places == Some(0) && field.number_negative != Some(true) =>
+        {
+            (parse_quote!(usize), Some(USIZE_OR_NULL))
+        }
+        "number" => (parse_quote!(f64), Some(FLOAT_OR_NULL)),
+        "count" | "autonumber" | "rating" => (parse_quote!(usize), Some(USIZE_OR_NULL)),
+        "date" if field.date_include_time != Some(true) => (parse_quote!(chrono::NaiveDate), None),
+        "date" | "created_on" | "last_modified" => {
+            (parse_quote!(chrono::DateTime<chrono::Local>), None)
+        }
+        _ => (parse_quote!(serde_json::Value), None),
+    }
+}
+
+/// An enum with one variant per option, read from and written as the object Baserow uses for
+/// the selected option.
+fn select_enum(name: &syn::Ident, options: &[SelectOption]) -> Item {
+    let mut variant_names = HashSet::new();
+    let variants: Vec<_> = options
+        .iter()
+        .map(|option| {
+            let mut variant = identifier(&option.value, Case::UpperCamel, "Value");
+            if !variant_names.insert(variant.clone()) {
+                variant = format!("{variant}{}", option.id);
+            }
+            let variant = format_ident!("{variant}");
+            let value = &option.value;
+            quote::quote! {
+                #[serde(rename = #value)]
+                #[strum(serialize = #value)]
+                #variant { color: String, id: usize }
+            }
+        })
+        .collect();
+    parse_quote! {
+        #[derive(
+            Serialize,
+            Deserialize,
+            Debug,
+            Clone,
+            strum_macros::EnumString,
+            strum_macros::Display
+        )]
+        #[serde(tag = "value")]
+        pub enum #name {
+            #(#variants,)*
+        }
+    }
+}
+
+/// Turns `name` into an identifier in `case`, prefixed with `prefix` if it would not start
+/// with a letter.
+fn identifier(name: &str, case: Case, prefix: &str) -> String {
+    let words: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { ' ' })
+        .collect();
+    // Digits don't start a new word, so that for example `2nd` is kept together
+    let mut identifier = words
+        .with_boundaries(&[Boundary::SPACE, Boundary::LOWER_UPPER, Boundary::ACRONYM])
+        .to_case(case);
+    if !identifier.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        identifier = format!("{prefix}{identifier}");
+    }
+    if KEYWORDS.contains(&identifier.as_str()) {
+        identifier.push('_');
+    }
+    identifier
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::baserow::codegen::{generate, identifier, Error, Table};
+    use crate::baserow::structs::Field;
+    use convert_case::Case;
+    use std::fs::read_to_string;
+
+    fn table(name: &str, id: usize, file: &str) -> Table {
+        let fields: Vec<Field> = serde_json::from_str(&read_to_string(file).unwrap()).unwrap();
+        Table {
+            name: name.to_string(),
+            id,
+            fields,
+        }
+    }
+
+    fn parse(code: &str) -> syn::File {
+        syn::parse_file(code).expect("generated code should parse")
+    }
+
+    fn struct_member<'a>(file: &'a syn::File, name: &str, member: &str) -> &'a syn::Field {
+        file.items
+            .iter()
+            .find_map(|item| match item {
+                syn::Item::Struct(item) if item.ident == name => item
+                    .fields
+                    .iter()
+                    .find(|field| field.ident.as_ref().is_some_and(|ident| ident == member)),
+                _ => None,
+            })
+            .unwrap_or_else(|| panic!("no member {member} in {name}"))
+    }
+
+    fn type_of(field: &syn::Field) -> String {
+        let ty = &field.ty;
+        quote::quote!(#ty).to_string()
+    }
+
+    #[test]
+    fn test_generate_field_types() {
+        let code = generate(&[
+            table("Jira", 520652, "testdata/field_types1.json"),
+            table("Companies", 520298, "testdata/field_types2.json"),
+        ])
+        .unwrap();
+        let file = parse(&code);
+
+        let jira = struct_member(&file, "Jira", "jira_issue_id");
+        assert_eq!(type_of(jira), "Option < String >");
+        let created = struct_member(&file, "Jira", "created_date");
+        assert_eq!(
+            type_of(created),
+            "Option < chrono :: DateTime < chrono :: Local > >"
+        );
+        let active = struct_member(&file, "Companies", "active");
+        assert_eq!(type_of(active), "Option < bool >");
+        let count = struct_member(&file, "Companies", "count");
+        assert_eq!(type_of(count), "Option < usize >");
+        let mgr = struct_member(&file, "Companies", "tech_account_mgr");
+        assert_eq!(type_of(mgr), "Option < serde_json :: Value >");
+        let state = struct_member(&file, "Companies", "state");
+        assert_eq!(type_of(state), "Option < CompaniesState >");
+
+        assert!(code.contains("OfferInProgress { color: String, id: usize }"));
+        assert!(code.contains("#[strum(serialize = \"offer in progress\")]"));
+        assert!(code.contains("Identifier::Text"));
+        assert!(code.contains("\"field_4133237\".to_string()"));
+    }
+
+    #[test]
+    fn test_generate_offers() {
+        let code = generate(&[table(
+            "offers",
+            568215,
+            "testdata/baserow/offers_fields.json",
+        )])
+        .unwrap();
+        let file = parse(&code);
+
+        let easybill_id = struct_member(&file, "Offers", "easybill_id");
+        assert_eq!(type_of(easybill_id), "Option < usize >");
+        let amount = struct_member(&file, "Offers", "amount");
+        assert_eq!(type_of(amount), "Option < f64 >");
+        assert!(code.contains("deserialize_with = \"crate::baserow::de::float_or_null\""));
+        assert!(code.contains("Identifier::UnsignedNumber"));
+        assert!(code.contains("568215usize"));
+        assert!(code.contains("Accepted { color: String, id: usize }"));
+    }
+
+    #[test]
+    fn test_generate_without_primary_field() {
+        let mut offers = table("Offers", 568215, "testdata/baserow/offers_fields.json");
+        offers.fields.retain(|field| !field.primary);
+
+        assert!(matches!(
+            generate(&[offers]),
+            Err(Error::NoPrimaryField { table }) if table == "Offers"
+        ));
+    }
+
+    #[test]
+    fn test_identifier() {
+        assert_eq!(
+            identifier("Contract-URL", Case::Snake, "field_"),
+            "contract_url"
+        );
+        assert_eq!(identifier("type", Case::Snake, "field_"), "type_");
+        assert_eq!(
+            identifier("24/7 Support", Case::UpperCamel, "Value"),
+            "Value247Support"
+        );
+        assert_eq!(
+            identifier("2nd reviewer", Case::Snake, "field_"),
+            "field_2nd_reviewer"
+        );
+    }
+}
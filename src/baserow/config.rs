@@ -0,0 +1,70 @@
+use crate::baserow::client::{Error, ParseUrlSnafu};
+use reqwest::Url;
+use snafu::ResultExt;
+
+const DEFAULT_URL: &str = "https://api.baserow.io/";
+
+/// Tells the [`crate::baserow::client::Baserow`] client where to find the API and how to
+/// authenticate.
+///
+/// `token` is a database token, which grants access to the rows of the tables in one
+/// workspace.
+#[derive(Debug, Clone)]
+pub struct BaserowConfig {
+    /// Url of the Baserow instance, for example `https://api.baserow.io/`
+    pub url: Url,
+    pub token: String,
+}
+
+impl BaserowConfig {
+    pub fn new(url: &str, token: &str) -> Result<Self, Error> {
+        // Url::join would drop the last path segment of a url without a trailing slash
+        let url = if url.ends_with('/') {
+            url.to_string()
+        } else {
+            format!("{url}/")
+        };
+
+        Ok(Self {
+            url: Url::parse(&url).context(ParseUrlSnafu { url: &url })?,
+            token: token.to_string(),
+        })
+    }
+
+    /// Talks to the Baserow SaaS instance.
+    pub fn hosted(token: &str) -> Self {
+        Self::new(DEFAULT_URL, token).expect("default Baserow url should be valid")
+    }
+
+    /// Returns the url of the API endpoint `path`, for example `database/fields/table/1/`.
+    pub fn endpoint(&self, path: &str) -> Result<Url, Error> {
+        self.url
+            .join(&format!("api/{path}"))
+            .context(ParseUrlSnafu { url: path })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::baserow::config::BaserowConfig;
+
+    #[test]
+    fn test_endpoint() {
+        let config = BaserowConfig::new("https://baserow.example.com/base", "token").unwrap();
+
+        assert_eq!(
+            config
+                .endpoint("database/rows/table/568215/")
+                .unwrap()
+                .as_str(),
+            "https://baserow.example.com/base/api/database/rows/table/568215/"
+        );
+        assert_eq!(
+            BaserowConfig::hosted("token")
+                .endpoint("database/fields/table/1/")
+                .unwrap()
+                .as_str(),
+            "https://api.baserow.io/api/database/fields/table/1/"
+        );
+    }
+}
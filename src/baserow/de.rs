@@ -0,0 +1,165 @@
+//! Deserializers used by generated code.
+//!
+//! Baserow sends number fields as strings such as `"12000.00"`, counts as numbers and empty
+//! fields as `null`.
+
+use serde::de::{self, Visitor};
+use serde::Deserializer;
+use std::fmt;
+use std::marker::PhantomData;
+use std::str::FromStr;
+
+pub fn usize_or_null<'de, D>(deserializer: D) -> Result<Option<usize>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_any(NumberOrNull(PhantomData))
+}
+
+pub fn float_or_null<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_any(NumberOrNull(PhantomData))
+}
+
+/// Converts numbers, strings holding a number and `null` into `Option<T>`.
+struct NumberOrNull<T>(PhantomData<T>);
+
+/// Numbers that can be read from JSON numbers, without losing their sign or fraction.
+trait Number: FromStr + Sized {
+    fn from_u64(value: u64) -> Option<Self>;
+    fn from_i64(value: i64) -> Option<Self>;
+    fn from_f64(value: f64) -> Option<Self>;
+}
+
+impl Number for usize {
+    fn from_u64(value: u64) -> Option<Self> {
+        value.try_into().ok()
+    }
+
+    fn from_i64(value: i64) -> Option<Self> {
+        value.try_into().ok()
+    }
+
+    fn from_f64(value: f64) -> Option<Self> {
+        (value.fract() == 0.0 && value >= 0.0).then_some(value as usize)
+    }
+}
+
+impl Number for f64 {
+    fn from_u64(value: u64) -> Option<Self> {
+        Some(value as f64)
+    }
+
+    fn from_i64(value: i64) -> Option<Self> {
+        Some(value as f64)
+    }
+
+    fn from_f64(value: f64) -> Option<Self> {
+        Some(value)
+    }
+}
+
+impl<'de, T> Visitor<'de> for NumberOrNull<T>
+where
+    T: Number,
+{
+    type Value = Option<T>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("number or null")
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        if value.is_empty() {
+            return Ok(None);
+        }
+        value
+            .parse()
+            .map(Some)
+            .map_err(|_| E::invalid_value(de::Unexpected::Str(value), &self))
+    }
+
+    fn visit_u64<E>(self, value: u64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        T::from_u64(value)
+            .map(Some)
+            .ok_or_else(|| E::invalid_value(de::Unexpected::Unsigned(value), &self))
+    }
+
+    fn visit_i64<E>(self, value: i64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        T::from_i64(value)
+            .map(Some)
+            .ok_or_else(|| E::invalid_value(de::Unexpected::Signed(value), &self))
+    }
+
+    fn visit_f64<E>(self, value: f64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        T::from_f64(value)
+            .map(Some)
+            .ok_or_else(|| E::invalid_value(de::Unexpected::Float(value), &self))
+    }
+
+    fn visit_unit<E>(self) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(None)
+    }
+
+    fn visit_none<E>(self) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::baserow::de::{float_or_null, usize_or_null};
+    use serde::Deserialize;
+
+    #[derive(Deserialize)]
+    struct Numbers {
+        #[serde(default, deserialize_with = "usize_or_null")]
+        count: Option<usize>,
+        #[serde(default, deserialize_with = "float_or_null")]
+        amount: Option<f64>,
+    }
+
+    fn parse(json: &str) -> Result<(Option<usize>, Option<f64>), serde_json::Error> {
+        let numbers: Numbers = serde_json::from_str(json)?;
+        Ok((numbers.count, numbers.amount))
+    }
+
+    #[test]
+    fn test_numbers_or_null() {
+        assert_eq!(
+            parse(r#"{"count": "123", "amount": "12000.00"}"#).unwrap(),
+            (Some(123), Some(12000.0))
+        );
+        assert_eq!(
+            parse(r#"{"count": 3, "amount": -2.5}"#).unwrap(),
+            (Some(3), Some(-2.5))
+        );
+        assert_eq!(
+            parse(r#"{"count": null, "amount": ""}"#).unwrap(),
+            (None, None)
+        );
+        assert_eq!(parse("{}").unwrap(), (None, None));
+        assert!(parse(r#"{"count": "-1"}"#).is_err());
+        assert!(parse(r#"{"amount": "a lot"}"#).is_err());
+    }
+}
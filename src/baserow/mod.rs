@@ -0,0 +1,5 @@
+pub mod client;
+pub mod codegen;
+pub mod config;
+pub mod de;
+pub mod structs;
@@ -0,0 +1,53 @@
+use serde::Deserialize;
+
+/// One page of a Baserow list endpoint.
+#[derive(Debug, Deserialize)]
+pub struct Page<T> {
+    pub count: usize,
+    /// Url of the next page, unset on the last one
+    pub next: Option<String>,
+    pub results: Vec<T>,
+}
+
+/// A row of a table, with the fields of type `T` next to the ID of the row.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Row<T> {
+    pub id: usize,
+    #[serde(flatten)]
+    pub fields: T,
+}
+
+/// A field (column) of a Baserow table.
+///
+/// Only the properties needed to generate code are read, Baserow sends many more depending on
+/// the type of the field.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Field {
+    pub id: usize,
+    pub table_id: usize,
+    pub name: String,
+    /// For example `text`, `number` or `single_select`
+    #[serde(rename = "type")]
+    pub field_type: String,
+    /// Set for the field that identifies rows
+    pub primary: bool,
+    pub select_options: Option<Vec<SelectOption>>,
+    pub number_decimal_places: Option<u32>,
+    pub number_negative: Option<bool>,
+    pub date_include_time: Option<bool>,
+}
+
+impl Field {
+    /// Returns the name the field has in rows read and written without user field names.
+    pub fn key(&self) -> String {
+        format!("field_{}", self.id)
+    }
+}
+
+/// A value a single select field can take.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SelectOption {
+    pub id: usize,
+    pub value: String,
+    pub color: String,
+}
@@ -0,0 +1,243 @@
+use crate::bdwh::{Error, InvalidEnvSnafu, ParseConfigSnafu, ReadConfigSnafu};
+use serde::Deserialize;
+use snafu::ResultExt;
+use std::path::{Path, PathBuf};
+
+/// Tells [`crate::bdwh::Bdwh`] how to reach Trino.
+///
+/// The defaults match a Trino that is port-forwarded to `localhost:8443`, except that TLS
+/// certificates are verified. Disable `tls.verify` or point `tls.ca_bundle` at the cluster's CA
+/// for self-signed certificates. The tables the migration view is built from can be changed
+/// in `sources`.
+///
+/// ```toml
+/// host = "trino.example.com"
+/// port = 443
+/// username = "jane"
+///
+/// [auth]
+/// method = "basic"
+/// password = "..."
+///
+/// [tls]
+/// ca_bundle = "/etc/ssl/stackable-ca.pem"
+///
+/// [sources]
+/// report_times = "lakehouse.bdwh.vw_stc_report_times_bill_l3y"
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct BdwhConfig {
+    pub host: String,
+    pub port: u16,
+    pub catalog: String,
+    pub schema: String,
+    /// User queries are executed as, also used for authentication
+    pub username: String,
+    pub auth: BdwhAuth,
+    pub tls: TlsConfig,
+    pub sources: SourceTables,
+}
+
+/// How to authenticate against Trino.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "method", rename_all = "lowercase")]
+pub enum BdwhAuth {
+    None,
+    Basic { password: String },
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct TlsConfig {
+    /// Connects via https if set
+    pub enabled: bool,
+    /// Checks the server certificate if set
+    pub verify: bool,
+    /// PEM file with the CA that signed the server certificate, if it's not a public one
+    pub ca_bundle: Option<PathBuf>,
+}
+
+/// The fully qualified names of the tables the migration view joins, see
+/// [`crate::bdwh::view`].
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct SourceTables {
+    /// The BDWH time reports
+    pub report_times: String,
+    /// The sheet mapping BANs to CoffeeCup projects and tasks
+    pub ban_mapping: String,
+    /// The sheet mapping persons to CoffeeCup users and teams
+    pub persons: String,
+}
+
+impl Default for BdwhConfig {
+    fn default() -> Self {
+        Self {
+            host: "localhost".to_string(),
+            port: 8443,
+            catalog: "lakehouse".to_string(),
+            schema: "bdwh".to_string(),
+            username: String::new(),
+            auth: BdwhAuth::None,
+            tls: TlsConfig::default(),
+            sources: SourceTables::default(),
+        }
+    }
+}
+
+impl Default for TlsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            verify: true,
+            ca_bundle: None,
+        }
+    }
+}
+
+impl Default for SourceTables {
+    fn default() -> Self {
+        Self {
+            report_times: "lakehouse.bdwh.vw_stc_report_times_bill_l3y".to_string(),
+            ban_mapping: "gsheet.default.ban_cc".to_string(),
+            persons: "gsheet.default.persons".to_string(),
+        }
+    }
+}
+
+impl BdwhConfig {
+    /// Reads the config from a TOML file, settings missing in the file keep their defaults.
+    pub fn from_file(path: &Path) -> Result<Self, Error> {
+        let content = std::fs::read_to_string(path).context(ReadConfigSnafu {
+            path: path.display().to_string(),
+        })?;
+        toml::from_str(&content).context(ParseConfigSnafu {
+            path: path.display().to_string(),
+        })
+    }
+
+    /// Reads the config from `BDWH_*` environment variables, see [`Self::with_env_overrides`].
+    pub fn from_env() -> Result<Self, Error> {
+        Self::default().with_env_overrides()
+    }
+
+    /// Replaces settings with the environment variables that are set:
+    ///
+    /// `BDWH_HOST`, `BDWH_PORT`, `BDWH_CATALOG`, `BDWH_SCHEMA`, `BDWH_USERNAME`, `BDWH_PASSWORD`
+    /// (switches to basic auth), `BDWH_TLS` and `BDWH_TLS_VERIFY` (`true` or `false`) and
+    /// `BDWH_CA_BUNDLE`.
+    pub fn with_env_overrides(mut self) -> Result<Self, Error> {
+        if let Some(host) = env("BDWH_HOST") {
+            self.host = host;
+        }
+        if let Some(port) = env("BDWH_PORT") {
+            self.port = parse_env("BDWH_PORT", port)?;
+        }
+        if let Some(catalog) = env("BDWH_CATALOG") {
+            self.catalog = catalog;
+        }
+        if let Some(schema) = env("BDWH_SCHEMA") {
+            self.schema = schema;
+        }
+        if let Some(username) = env("BDWH_USERNAME") {
+            self.username = username;
+        }
+        if let Some(password) = env("BDWH_PASSWORD") {
+            self.auth = BdwhAuth::Basic { password };
+        }
+        if let Some(enabled) = env("BDWH_TLS") {
+            self.tls.enabled = parse_env("BDWH_TLS", enabled)?;
+        }
+        if let Some(verify) = env("BDWH_TLS_VERIFY") {
+            self.tls.verify = parse_env("BDWH_TLS_VERIFY", verify)?;
+        }
+        if let Some(ca_bundle) = env("BDWH_CA_BUNDLE") {
+            self.tls.ca_bundle = Some(PathBuf::from(ca_bundle));
+        }
+        Ok(self)
+    }
+
+    /// Returns the fully qualified name of `table` in the configured catalog and schema.
+    pub fn table(&self, table: &str) -> String {
+        format!("{}.{}.{table}", self.catalog, self.schema)
+    }
+}
+
+fn env(name: &str) -> Option<String> {
+    std::env::var(name).ok()
+}
+
+fn parse_env<T>(name: &str, value: String) -> Result<T, Error>
+where
+    T: std::str::FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    value
+        .parse()
+        .map_err(|error: T::Err| error.into())
+        .context(InvalidEnvSnafu { name, value })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bdwh::config::{parse_env, BdwhAuth, BdwhConfig};
+    use crate::bdwh::Error;
+
+    #[test]
+    fn test_parse_config() {
+        let config: BdwhConfig = toml::from_str(
+            r#"
+            host = "trino.example.com"
+            port = 443
+            username = "jane"
+
+            [auth]
+            method = "basic"
+            password = "secret"
+
+            [tls]
+            ca_bundle = "/etc/ssl/stackable-ca.pem"
+
+            [sources]
+            persons = "gsheet.staging.persons"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.host, "trino.example.com");
+        assert_eq!(config.port, 443);
+        assert_eq!(config.catalog, "lakehouse");
+        assert!(matches!(&config.auth, BdwhAuth::Basic { password } if password == "secret"));
+        assert!(config.tls.enabled);
+        assert!(config.tls.verify);
+        assert_eq!(
+            config.table("project_migration"),
+            "lakehouse.bdwh.project_migration"
+        );
+        assert_eq!(config.sources.persons, "gsheet.staging.persons");
+        assert_eq!(config.sources.ban_mapping, "gsheet.default.ban_cc");
+    }
+
+    #[test]
+    fn test_default_config() {
+        let config: BdwhConfig = toml::from_str("").unwrap();
+
+        assert_eq!(config.host, "localhost");
+        assert_eq!(config.port, 8443);
+        assert!(matches!(config.auth, BdwhAuth::None));
+    }
+
+    #[test]
+    fn test_parse_env() {
+        assert_eq!(
+            parse_env::<u16>("BDWH_PORT", "443".to_string()).unwrap(),
+            443
+        );
+
+        let error = parse_env::<u16>("BDWH_PORT", "84431".to_string()).unwrap_err();
+        assert!(matches!(&error, Error::InvalidEnv { name, .. } if name == "BDWH_PORT"));
+        let source = std::error::Error::source(&error).unwrap();
+        assert_eq!(source.to_string(), "number too large to fit in target type");
+    }
+}
@@ -1,11 +1,17 @@
+pub mod config;
+pub mod query;
 pub mod structs;
+pub mod view;
 
-use crate::bdwh::structs::TimeEntry;
-use crate::bdwh::Error::ExecuteSql;
-use snafu::{ResultExt, Snafu};
-use std::{io, vec};
+use crate::bdwh::config::{BdwhAuth, BdwhConfig, SourceTables};
+use crate::bdwh::query::Select;
+use crate::bdwh::structs::{DateRange, EntryFilter, MonthlyHours, TimeEntry};
+use crate::bdwh::view::{Column, ColumnDrift};
+use futures::{future, stream, Stream, StreamExt, TryStreamExt};
+use snafu::{ensure, ResultExt, Snafu};
 use trino_rust_client::auth::Auth;
-use trino_rust_client::{Client, ClientBuilder, Row, Trino};
+use trino_rust_client::ssl::{Certificate, Ssl};
+use trino_rust_client::{Client, ClientBuilder, Trino};
 
 #[derive(Snafu, Debug)]
 pub enum Error {
@@ -14,62 +20,281 @@ pub enum Error {
         source: trino_rust_client::error::Error,
         stmt: String,
     },
+    #[snafu(display("View [{view}] does not exist"))]
+    MissingView { view: String },
+    #[snafu(display(
+        "View [{view}] does not match the time entries read from it, missing columns: {:?}, unexpected columns: {:?}",
+        drift.missing,
+        drift.unexpected
+    ))]
+    ViewDrift { view: String, drift: ColumnDrift },
+    #[snafu(display("Failed to read BDWH config [{path}]: {source}"))]
+    ReadConfig {
+        source: std::io::Error,
+        path: String,
+    },
+    #[snafu(display("Failed to parse BDWH config [{path}]: {source}"))]
+    ParseConfig {
+        source: toml::de::Error,
+        path: String,
+    },
+    #[snafu(display("Invalid value [{value}] for environment variable [{name}]: {source}"))]
+    InvalidEnv {
+        source: Box<dyn std::error::Error + Send + Sync>,
+        name: String,
+        value: String,
+    },
+    #[snafu(display("No BDWH username configured, set it in the config or as [BDWH_USERNAME]"))]
+    MissingUsername,
+    #[snafu(display("Failed to read CA bundle [{path}]: {source}"))]
+    ReadCaBundle {
+        source: std::io::Error,
+        path: String,
+    },
+    #[snafu(display("Failed to parse CA bundle [{path}]: {source}"))]
+    ParseCaBundle {
+        source: trino_rust_client::error::Error,
+        path: String,
+    },
+    #[snafu(display("Failed to set up Trino client for [{host}:{port}]: {source}"))]
+    BuildClient {
+        source: trino_rust_client::error::Error,
+        host: String,
+        port: u16,
+    },
 }
 
+/// Reads time entries from the BDWH via Trino.
+///
+/// The Trino client follows the `nextUri` of a query until its last page and returns all rows
+/// at once, so every query is held in memory as a whole. Large ranges are therefore read with
+/// [`Bdwh::entries_by_month`], which bounds memory use by the rows of a single month.
 pub struct Bdwh {
     client: Client,
+    catalog: String,
+    schema: String,
+    migration_view: String,
+    sources: SourceTables,
 }
 
 impl Bdwh {
-    pub fn new(username: &str, password: &str) -> Self {
-        let client = ClientBuilder::new(username, "localhost")
-            .port(8443)
-            .catalog("lakehouse")
-            .schema("bdwh")
-            .auth(Auth::new_basic(username, Some(password)))
-            .secure(true)
-            .no_verify(true)
-            .build()
-            .unwrap();
-        Bdwh { client }
+    pub fn new(config: &BdwhConfig) -> Result<Self, Error> {
+        ensure!(!config.username.is_empty(), MissingUsernameSnafu);
+
+        let mut builder = ClientBuilder::new(&config.username, &config.host)
+            .port(config.port)
+            .catalog(&config.catalog)
+            .schema(&config.schema)
+            .secure(config.tls.enabled)
+            .no_verify(!config.tls.verify);
+        if let BdwhAuth::Basic { password } = &config.auth {
+            builder = builder.auth(Auth::new_basic(&config.username, Some(password)));
+        }
+        if let Some(ca_bundle) = &config.tls.ca_bundle {
+            let path = ca_bundle.display().to_string();
+            let pem = std::fs::read(ca_bundle).context(ReadCaBundleSnafu { path: &path })?;
+            let root_cert = Certificate::from_pem(&pem).context(ParseCaBundleSnafu { path })?;
+            builder = builder.ssl(Ssl {
+                root_cert: Some(root_cert),
+            });
+        }
+        let client = builder.build().context(BuildClientSnafu {
+            host: &config.host,
+            port: config.port,
+        })?;
+
+        Ok(Bdwh {
+            client,
+            catalog: config.catalog.clone(),
+            schema: config.schema.clone(),
+            migration_view: config.table(view::NAME),
+            sources: config.sources.clone(),
+        })
     }
 
-    pub async fn get_entries_for_ban(&self, ban: &str) -> Result<Vec<TimeEntry>, Error> {
-        let sql = format!(
-            "select * from lakehouse.bdwh.project_migration where ban = '{}'",
-            ban
+    /// Creates the view time entries are read from, or replaces it if it exists already.
+    pub async fn create_migration_view(&self) -> Result<(), Error> {
+        self.execute(view::create_statement(&self.migration_view, &self.sources))
+            .await
+    }
+
+    /// Updates the view with the current BDWH data and mappings.
+    pub async fn refresh_migration_view(&self) -> Result<(), Error> {
+        self.execute(view::refresh_statement(&self.migration_view))
+            .await
+    }
+
+    /// Fails if the view is missing or its columns don't match [`TimeEntry`].
+    ///
+    /// Meant to be called before reading entries, so that schema changes are reported as such
+    /// rather than as rows that can't be deserialized.
+    pub async fn check_migration_view(&self) -> Result<(), Error> {
+        let columns: Vec<Column> = self
+            .get_all(&view::columns_query(
+                &self.catalog,
+                &self.schema,
+                view::NAME,
+            ))
+            .await?;
+        ensure!(
+            !columns.is_empty(),
+            MissingViewSnafu {
+                view: &self.migration_view
+            }
+        );
+
+        let drift = view::compare_columns(&columns);
+        ensure!(
+            drift.is_empty(),
+            ViewDriftSnafu {
+                view: &self.migration_view,
+                drift
+            }
         );
+        Ok(())
+    }
+
+    pub async fn get_entries_for_ban(&self, ban: &str) -> Result<Vec<TimeEntry>, Error> {
+        self.get_entries(&EntryFilter::default().ban(ban)).await
+    }
+
+    /// Retrieves all time entries matching `filter`, ordered by date and time.
+    pub async fn get_entries(&self, filter: &EntryFilter) -> Result<Vec<TimeEntry>, Error> {
+        self.get_all(&entries_query(&self.migration_view, filter))
+            .await
+    }
+
+    /// Retrieves the time entries matching `filter` in batches of one calendar month.
+    ///
+    /// Each month is a separate query that is read completely before it is yielded, so a
+    /// month with more rows than fit into memory can't be read this way either. Batches
+    /// are cut by date rather than by position, so rows added or removed by a refresh of the
+    /// view while reading can't shift other rows into a batch that has already been read.
+    /// Months without rows are skipped.
+    pub async fn entries_by_month<'a>(
+        &'a self,
+        filter: &EntryFilter,
+    ) -> Result<impl Stream<Item = Result<Vec<TimeEntry>, Error>> + 'a, Error> {
+        let range = self
+            .get_all::<DateRange>(&date_range_query(&self.migration_view, filter))
+            .await?
+            .into_iter()
+            .next()
+            .unwrap_or_default();
+        let months = match (range.first_date, range.last_date) {
+            (Some(first), Some(last)) => filter.months(first, last),
+            _ => Vec::new(),
+        };
+
+        Ok(stream::iter(months)
+            .then(move |month| async move { self.get_entries(&month).await })
+            .try_filter(|batch| future::ready(!batch.is_empty())))
+    }
+
+    /// Sums up the hours matching `filter` per BAN, person and month.
+    pub async fn get_monthly_hours(
+        &self,
+        filter: &EntryFilter,
+    ) -> Result<Vec<MonthlyHours>, Error> {
+        self.get_all(&monthly_hours_query(&self.migration_view, filter))
+            .await
+    }
+
+    async fn execute(&self, sql: String) -> Result<(), Error> {
+        self.client
+            .execute(sql.clone())
+            .await
+            .context(ExecuteSqlSnafu { stmt: sql })?;
+        Ok(())
+    }
+
+    async fn get_all<T>(&self, query: &Select) -> Result<Vec<T>, Error>
+    where
+        T: Trino + 'static,
+    {
+        let sql = query.to_string();
 
         Ok(self
             .client
-            .get_all::<TimeEntry>(sql.clone().into())
+            .get_all::<T>(sql.clone())
             .await
             .context(ExecuteSqlSnafu { stmt: sql })?
             .into_vec())
     }
 }
 
+fn entries_query(view: &str, filter: &EntryFilter) -> Select {
+    filter.apply(Select::from(view)).order_by(&[
+        "date",
+        "start_time",
+        "end_time",
+        "cc_id",
+        "ban",
+        "cc_project",
+        "cc_task",
+        "cc_team",
+        "comment",
+        "total_time",
+        "billable_time",
+    ])
+}
+
+fn date_range_query(view: &str, filter: &EntryFilter) -> Select {
+    filter.apply(Select::from(view).columns(&["min(date) as first_date", "max(date) as last_date"]))
+}
+
+fn monthly_hours_query(view: &str, filter: &EntryFilter) -> Select {
+    filter
+        .apply(Select::from(view).columns(&[
+            "ban",
+            "cc_id",
+            "date_trunc('month', date) as month",
+            "sum(total_time) as total_time",
+            "sum(billable_time) as billable_time",
+        ]))
+        .group_by(&["ban", "cc_id", "date_trunc('month', date)"])
+        .order_by(&["month", "ban", "cc_id"])
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::bdwh::Bdwh;
-    use baserow_client::client::Client;
-    use crate::coffeecup::client::CoffeeCup;
-    use crate::coffeecup::structs::TimeEntryWrapper;
-
-    #[tokio::test]
-    async fn test_get_times() {
-        let client = Bdwh::new("admin", "Elated-Putt-Aerobics7-Showy-Stem");
-        let cc_client = CoffeeCup::new("").await.unwrap();
-
-        let mut test = client.get_entries_for_ban("001105").await.unwrap();
-
-        for entry in test {
-            let cc_entry: crate::coffeecup::structs::TimeEntryWrapper = entry.into();
-            println!("{}", serde_json::to_string(&cc_entry).unwrap());
-            cc_client.create_timeentry(&cc_entry).await.unwrap();
-            println!("Successfully created entry: {}: {}", cc_entry.timeEntry.day, cc_entry.timeEntry.duration)
-        }
+    use crate::bdwh::structs::EntryFilter;
+    use crate::bdwh::{date_range_query, entries_query, monthly_hours_query};
+
+    #[test]
+    fn test_entries_query() {
+        let filter = EntryFilter::month(2025, 6).unwrap().cc_id("42");
+
+        assert_eq!(
+            entries_query("lakehouse.bdwh.project_migration", &filter).to_string(),
+            "select * from \"lakehouse\".\"bdwh\".\"project_migration\" \
+            where \"cc_id\" = '42' and \"date\" >= DATE '2025-06-01' \
+            and \"date\" <= DATE '2025-06-30' order by date, start_time, end_time, cc_id, \
+            ban, cc_project, cc_task, cc_team, comment, total_time, billable_time"
+        );
     }
 
+    #[test]
+    fn test_date_range_query() {
+        let filter = EntryFilter::default().ban("001105");
 
-}
\ No newline at end of file
+        assert_eq!(
+            date_range_query("project_migration", &filter).to_string(),
+            "select min(date) as first_date, max(date) as last_date \
+            from \"project_migration\" where \"ban\" = '001105'"
+        );
+    }
+
+    #[test]
+    fn test_monthly_hours_query() {
+        let filter = EntryFilter::default().ban("001105").cc_team("3");
+
+        assert_eq!(
+            monthly_hours_query("project_migration", &filter).to_string(),
+            "select ban, cc_id, date_trunc('month', date) as month, \
+            sum(total_time) as total_time, sum(billable_time) as billable_time \
+            from \"project_migration\" where \"ban\" = '001105' and \"cc_team\" = '3' \
+            group by ban, cc_id, date_trunc('month', date) order by month, ban, cc_id"
+        );
+    }
+}
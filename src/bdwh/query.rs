@@ -0,0 +1,291 @@
+//! Builds the SQL sent to Trino.
+//!
+//! Trino has no bind parameters in its REST protocol, so every value ends up in the statement
+//! text. Values are therefore only ever added as [`Literal`]s, which are escaped when the
+//! statement is rendered, and identifiers are always quoted. Raw SQL fragments (select
+//! expressions, `group by`, `order by`) are `&'static str` so that they can't be assembled from
+//! input at runtime.
+
+use chrono::NaiveDate;
+use std::fmt::{Display, Formatter};
+
+/// A value compared against in a query.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Literal {
+    String(String),
+    Integer(i64),
+    Date(NaiveDate),
+    Boolean(bool),
+}
+
+impl Display for Literal {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            // Trino does not treat backslashes specially, doubling quotes is all it takes
+            Literal::String(value) => write!(f, "'{}'", value.replace('\'', "''")),
+            Literal::Integer(value) => write!(f, "{value}"),
+            Literal::Date(value) => write!(f, "DATE '{}'", value.format("%Y-%m-%d")),
+            Literal::Boolean(value) => write!(f, "{value}"),
+        }
+    }
+}
+
+impl From<&str> for Literal {
+    fn from(value: &str) -> Self {
+        Literal::String(value.to_string())
+    }
+}
+
+impl From<String> for Literal {
+    fn from(value: String) -> Self {
+        Literal::String(value)
+    }
+}
+
+impl From<&String> for Literal {
+    fn from(value: &String) -> Self {
+        Literal::String(value.clone())
+    }
+}
+
+impl From<i64> for Literal {
+    fn from(value: i64) -> Self {
+        Literal::Integer(value)
+    }
+}
+
+impl From<NaiveDate> for Literal {
+    fn from(value: NaiveDate) -> Self {
+        Literal::Date(value)
+    }
+}
+
+impl From<bool> for Literal {
+    fn from(value: bool) -> Self {
+        Literal::Boolean(value)
+    }
+}
+
+/// How a column is compared to a [`Literal`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Comparison {
+    Eq,
+    NotEq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+}
+
+impl Display for Comparison {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Comparison::Eq => "=",
+            Comparison::NotEq => "<>",
+            Comparison::Lt => "<",
+            Comparison::LtEq => "<=",
+            Comparison::Gt => ">",
+            Comparison::GtEq => ">=",
+        })
+    }
+}
+
+/// Quotes a possibly qualified name like `lakehouse.bdwh.project_migration`.
+///
+/// Every part separated by a dot is quoted on its own, quotes within a part are doubled.
+pub fn quote_identifier(name: &str) -> String {
+    name.split('.')
+        .map(|part| format!("\"{}\"", part.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// A `select` statement.
+#[derive(Debug, Clone)]
+pub struct Select {
+    table: String,
+    columns: Vec<&'static str>,
+    conditions: Vec<String>,
+    group_by: Vec<&'static str>,
+    order_by: Vec<&'static str>,
+}
+
+impl Select {
+    /// Selects all columns from `table`, see [`quote_identifier`] for qualified names.
+    pub fn from(table: &str) -> Self {
+        Self {
+            table: quote_identifier(table),
+            columns: Vec::new(),
+            conditions: Vec::new(),
+            group_by: Vec::new(),
+            order_by: Vec::new(),
+        }
+    }
+
+    /// Selects `columns` instead of `*`, these may be any SQL expression.
+    pub fn columns(mut self, columns: &[&'static str]) -> Self {
+        self.columns.extend_from_slice(columns);
+        self
+    }
+
+    /// Only returns rows where `column` compares to `value` as requested.
+    pub fn filter(
+        mut self,
+        column: &str,
+        comparison: Comparison,
+        value: impl Into<Literal>,
+    ) -> Self {
+        self.conditions.push(format!(
+            "{} {comparison} {}",
+            quote_identifier(column),
+            value.into()
+        ));
+        self
+    }
+
+    /// Only returns rows where `column` equals `value`.
+    pub fn filter_eq(self, column: &str, value: impl Into<Literal>) -> Self {
+        self.filter(column, Comparison::Eq, value)
+    }
+
+    /// Only returns rows where `column` equals one of `values`.
+    ///
+    /// An empty `values` matches no rows at all.
+    pub fn filter_in<T>(mut self, column: &str, values: impl IntoIterator<Item = T>) -> Self
+    where
+        T: Into<Literal>,
+    {
+        let values: Vec<String> = values
+            .into_iter()
+            .map(|value| value.into().to_string())
+            .collect();
+        if values.is_empty() {
+            self.conditions.push("false".to_string());
+        } else {
+            self.conditions.push(format!(
+                "{} in ({})",
+                quote_identifier(column),
+                values.join(", ")
+            ));
+        }
+        self
+    }
+
+    pub fn group_by(mut self, expressions: &[&'static str]) -> Self {
+        self.group_by.extend_from_slice(expressions);
+        self
+    }
+
+    pub fn order_by(mut self, expressions: &[&'static str]) -> Self {
+        self.order_by.extend_from_slice(expressions);
+        self
+    }
+}
+
+impl Display for Select {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let columns = if self.columns.is_empty() {
+            "*".to_string()
+        } else {
+            self.columns.join(", ")
+        };
+        write!(f, "select {columns} from {}", self.table)?;
+        if !self.conditions.is_empty() {
+            write!(f, " where {}", self.conditions.join(" and "))?;
+        }
+        if !self.group_by.is_empty() {
+            write!(f, " group by {}", self.group_by.join(", "))?;
+        }
+        if !self.order_by.is_empty() {
+            write!(f, " order by {}", self.order_by.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bdwh::query::{quote_identifier, Comparison, Literal, Select};
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_literals() {
+        assert_eq!(Literal::from("001105").to_string(), "'001105'");
+        assert_eq!(Literal::from(42).to_string(), "42");
+        assert_eq!(Literal::from(true).to_string(), "true");
+        assert_eq!(
+            Literal::from(NaiveDate::from_ymd_opt(2025, 6, 1).unwrap()).to_string(),
+            "DATE '2025-06-01'"
+        );
+    }
+
+    #[test]
+    fn test_hostile_literals() {
+        assert_eq!(
+            Literal::from("001105' or '1'='1").to_string(),
+            "'001105'' or ''1''=''1'"
+        );
+        assert_eq!(
+            Literal::from("'; drop table project_migration; --").to_string(),
+            "'''; drop table project_migration; --'"
+        );
+        // A backslash does not escape the closing quote in Trino, so it stays as it is
+        assert_eq!(Literal::from("a\\'b").to_string(), "'a\\''b'");
+        assert_eq!(Literal::from("Müller'\n").to_string(), "'Müller''\n'");
+    }
+
+    #[test]
+    fn test_quote_identifier() {
+        assert_eq!(
+            quote_identifier("lakehouse.bdwh.project_migration"),
+            "\"lakehouse\".\"bdwh\".\"project_migration\""
+        );
+        assert_eq!(
+            quote_identifier("ban\" = ban or \"1"),
+            "\"ban\"\" = ban or \"\"1\""
+        );
+    }
+
+    #[test]
+    fn test_select() {
+        let select = Select::from("lakehouse.bdwh.project_migration")
+            .filter_eq("ban", "001105")
+            .filter(
+                "date",
+                Comparison::GtEq,
+                NaiveDate::from_ymd_opt(2025, 6, 1).unwrap(),
+            )
+            .order_by(&["date", "start_time"]);
+
+        assert_eq!(
+            select.to_string(),
+            "select * from \"lakehouse\".\"bdwh\".\"project_migration\" \
+            where \"ban\" = '001105' and \"date\" >= DATE '2025-06-01' \
+            order by date, start_time"
+        );
+    }
+
+    #[test]
+    fn test_select_aggregate() {
+        let select = Select::from("project_migration")
+            .columns(&["ban", "sum(total_time) as total_time"])
+            .filter_in("ban", ["001105", "x') or ('1'='1"])
+            .group_by(&["ban"]);
+
+        assert_eq!(
+            select.to_string(),
+            "select ban, sum(total_time) as total_time from \"project_migration\" \
+            where \"ban\" in ('001105', 'x'') or (''1''=''1') group by ban"
+        );
+    }
+
+    #[test]
+    fn test_filter_in_nothing() {
+        let select = Select::from("project_migration").filter_in::<&str>("ban", []);
+
+        assert_eq!(
+            select.to_string(),
+            "select * from \"project_migration\" where false"
+        );
+    }
+}
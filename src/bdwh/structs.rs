@@ -1,63 +1,16 @@
-use chrono::{Date, DateTime, FixedOffset, NaiveDate, NaiveTime};
-use serde::Deserialize;
-use trino_rust_client::{Client as TrinoClient, Trino};
-
-
-
-
-/*
-Following materialized view can be used with this data structure
-
-create or replace materialized view lakehouse.bdwh.project_migration as
-select
-	v.dys_date as date ,
-	cast(v.total_time as double) as total_time,
-	cast(v.billable_time as double) as billable_time,
-	v.start_time ,
-	v.end_time,
-	p.cc_id,
-	p.cc_team,
-	b.cc_project,
-	b.cc_task,
-	b.ban,
-	v.comment
-from
-	lakehouse.bdwh.vw_stc_report_times_bill_l3y v,
-	gsheet."default".ban_cc b ,
-	gsheet."default".persons p
-where
-	v.pma_person_code = p.code
-	and v.por_bt_internal_order_id = b.ban
-	
-	https://stackable.coffeecup.app/v1/export/timeEntries?
-	start_date=2025-06-01T00:00:00.000Z&
-	end_date=2025-06-30T23:59:59.999Z&
-	title=Hours Overview 01.06.2025 - 30.06.2025&
-	duration_format=0&
-	include_time_tracked=true&
-	include_time_rounded=false&
-	include_amount_rounded=false&
-	columns[client]=true&
-	columns[project]=true&
-	columns[task]=true&
-	columns[user]=true&
-	columns[comment]=true&
-	columns[team]=true&
-	columns[reference]=true&
-	columns[date]=true&
-	columns[start_end]=false&
-	group_by=date&
-	show_only_summary_headers=false&
-	format=pdf&
-	access_token=da862de7c3ca55fc681319f7c99c543c10f15085
-	
- */
-
-#[derive(Trino, Debug)]
+use crate::bdwh::query::{Comparison, Select};
+use crate::dates::month_bounds;
+use chrono::{Datelike, Months, NaiveDate, NaiveTime};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use trino_rust_client::Trino;
+
+#[derive(Trino, Debug, Clone)]
 pub struct TimeEntry {
     pub date: NaiveDate,
-    pub start_time: NaiveTime,
-    pub end_time: NaiveTime,
+    /// Unset for rows that are only known by day, such as those imported from CSV exports
+    pub start_time: Option<NaiveTime>,
+    pub end_time: Option<NaiveTime>,
     pub total_time: f64,
     pub billable_time: f64,
     pub cc_project: String,
@@ -67,3 +20,227 @@ pub struct TimeEntry {
     pub cc_task: String,
     pub comment: String,
 }
+
+/// Hours booked by one person on one BAN within one month.
+#[derive(Trino, Debug, Clone, Serialize)]
+pub struct MonthlyHours {
+    pub ban: String,
+    pub cc_id: String,
+    /// First day of the month
+    pub month: NaiveDate,
+    pub total_time: f64,
+    pub billable_time: f64,
+}
+
+/// The first and last day with rows matching a filter, both `None` if there are none.
+#[derive(Trino, Debug, Clone, Default)]
+pub struct DateRange {
+    pub first_date: Option<NaiveDate>,
+    pub last_date: Option<NaiveDate>,
+}
+
+/// Restricts which rows are read from BDWH.
+#[derive(Debug, Clone, Default)]
+pub struct EntryFilter {
+    pub ban: Option<String>,
+    /// The person as mapped to CoffeeCup, see [`TimeEntry::cc_id`]
+    pub cc_id: Option<String>,
+    pub cc_team: Option<String>,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+impl EntryFilter {
+    /// Matches all rows within the given month, `None` if there is no such month.
+    pub fn month(year: i32, month: u32) -> Option<Self> {
+        let (from, to) = month_bounds(year, month)?;
+        Some(Self {
+            from: Some(from),
+            to: Some(to),
+            ..Self::default()
+        })
+    }
+
+    pub fn ban(mut self, ban: impl Into<String>) -> Self {
+        self.ban = Some(ban.into());
+        self
+    }
+
+    pub fn cc_id(mut self, cc_id: impl Into<String>) -> Self {
+        self.cc_id = Some(cc_id.into());
+        self
+    }
+
+    pub fn cc_team(mut self, cc_team: impl Into<String>) -> Self {
+        self.cc_team = Some(cc_team.into());
+        self
+    }
+
+    /// Splits the days from `first` to `last` into calendar months, keeping all other
+    /// criteria.
+    ///
+    /// The first and last filter only cover part of their month if `first` or `last` fall
+    /// within it.
+    pub fn months(&self, first: NaiveDate, last: NaiveDate) -> Vec<EntryFilter> {
+        let mut months = Vec::new();
+        let mut start = first.with_day(1).unwrap_or(first);
+        while start <= last {
+            let next = start.checked_add_months(Months::new(1));
+            let end = next
+                .and_then(|next| next.pred_opt())
+                .unwrap_or(NaiveDate::MAX);
+            months.push(Self {
+                from: Some(start.max(first)),
+                to: Some(end.min(last)),
+                ..self.clone()
+            });
+            let Some(next) = next else {
+                break;
+            };
+            start = next;
+        }
+        months
+    }
+
+    /// Adds the criteria to the `where` clause of `select`.
+    pub fn apply(&self, mut select: Select) -> Select {
+        if let Some(ban) = &self.ban {
+            select = select.filter_eq("ban", ban);
+        }
+        if let Some(cc_id) = &self.cc_id {
+            select = select.filter_eq("cc_id", cc_id);
+        }
+        if let Some(cc_team) = &self.cc_team {
+            select = select.filter_eq("cc_team", cc_team);
+        }
+        if let Some(from) = self.from {
+            select = select.filter("date", Comparison::GtEq, from);
+        }
+        if let Some(to) = self.to {
+            select = select.filter("date", Comparison::LtEq, to);
+        }
+        select
+    }
+}
+
+impl TimeEntry {
+    /// Columns the migration view needs to provide, one per field.
+    pub const COLUMNS: &'static [&'static str] = &[
+        "date",
+        "start_time",
+        "end_time",
+        "total_time",
+        "billable_time",
+        "cc_project",
+        "ban",
+        "cc_id",
+        "cc_team",
+        "cc_task",
+        "comment",
+    ];
+
+    /// Identifies this row independently of when and how often it is migrated.
+    ///
+    /// This is stored as `externalId` of the CoffeeCup entry created from the row, which allows
+    /// recognizing rows that have been migrated before. Rows without times of day share their
+    /// fingerprint with all rows of the same person on the same day with the same comment.
+    pub fn fingerprint(&self) -> String {
+        let mut hasher = Sha256::new();
+        for field in [
+            self.date.to_string(),
+            self.cc_id.clone(),
+            self.ban.clone(),
+            self.start_time
+                .map(|time| time.to_string())
+                .unwrap_or_default(),
+            self.end_time
+                .map(|time| time.to_string())
+                .unwrap_or_default(),
+            self.comment.clone(),
+        ] {
+            hasher.update(field.as_bytes());
+            // Separate fields so that moving text from one field to the next changes the hash
+            hasher.update([0x1f]);
+        }
+        let hash: String = hasher.finalize()[..16]
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect();
+        format!("bdwh-{hash}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bdwh::structs::EntryFilter;
+    use crate::coffeecup::mock::bdwh_entry;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_month_filter() {
+        let filter = EntryFilter::month(2024, 2).unwrap();
+        assert_eq!(filter.from, NaiveDate::from_ymd_opt(2024, 2, 1));
+        assert_eq!(filter.to, NaiveDate::from_ymd_opt(2024, 2, 29));
+
+        let filter = EntryFilter::month(2025, 12).unwrap();
+        assert_eq!(filter.to, NaiveDate::from_ymd_opt(2025, 12, 31));
+
+        assert!(EntryFilter::month(2025, 13).is_none());
+    }
+
+    #[test]
+    fn test_months() {
+        let filter = EntryFilter::default().ban("001105");
+        let first = NaiveDate::from_ymd_opt(2024, 12, 17).unwrap();
+        let last = NaiveDate::from_ymd_opt(2025, 2, 3).unwrap();
+
+        let months: Vec<(String, String, Option<String>)> = filter
+            .months(first, last)
+            .into_iter()
+            .map(|month| {
+                (
+                    month.from.unwrap().to_string(),
+                    month.to.unwrap().to_string(),
+                    month.ban,
+                )
+            })
+            .collect();
+
+        let ban = Some("001105".to_string());
+        assert_eq!(
+            months,
+            vec![
+                (
+                    "2024-12-17".to_string(),
+                    "2024-12-31".to_string(),
+                    ban.clone()
+                ),
+                (
+                    "2025-01-01".to_string(),
+                    "2025-01-31".to_string(),
+                    ban.clone()
+                ),
+                ("2025-02-01".to_string(), "2025-02-03".to_string(), ban),
+            ]
+        );
+        assert_eq!(filter.months(first, first).len(), 1);
+        assert!(filter.months(last, first).is_empty());
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let entry = bdwh_entry();
+
+        // Must never change, otherwise all previously migrated entries would be migrated again
+        assert_eq!(entry.fingerprint(), "bdwh-b2008777fe8e62bb4a5fc15420886f58");
+
+        let mut corrected = entry.clone();
+        corrected.total_time = 0.75;
+        corrected.cc_project = "11".to_string();
+        assert_eq!(corrected.fingerprint(), entry.fingerprint());
+
+        let mut other_person = entry.clone();
+        other_person.cc_id = "43".to_string();
+        assert_ne!(other_person.fingerprint(), entry.fingerprint());
+    }
+}
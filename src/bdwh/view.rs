@@ -0,0 +1,176 @@
+//! The materialized view [`TimeEntry`] rows are read from.
+//!
+//! It joins the BDWH time reports with the Google Sheets that map BANs to CoffeeCup projects
+//! and tasks and persons to CoffeeCup users and teams. Which tables these are is configured in
+//! [`SourceTables`].
+
+use crate::bdwh::config::SourceTables;
+use crate::bdwh::query::{quote_identifier, Select};
+use crate::bdwh::structs::TimeEntry;
+use trino_rust_client::Trino;
+
+/// Name of the view within the configured catalog and schema.
+pub const NAME: &str = "project_migration";
+
+/// The query the view is defined by, joining the tables in `sources`.
+fn query(sources: &SourceTables) -> String {
+    format!(
+        r#"select
+    v.dys_date as date,
+    cast(v.total_time as double) as total_time,
+    cast(v.billable_time as double) as billable_time,
+    v.start_time,
+    v.end_time,
+    p.cc_id,
+    p.cc_team,
+    b.cc_project,
+    b.cc_task,
+    b.ban,
+    v.comment
+from
+    {} v,
+    {} b,
+    {} p
+where
+    v.pma_person_code = p.code
+    and v.por_bt_internal_order_id = b.ban"#,
+        quote_identifier(&sources.report_times),
+        quote_identifier(&sources.ban_mapping),
+        quote_identifier(&sources.persons),
+    )
+}
+
+/// A row of `information_schema.columns`.
+#[derive(Trino, Debug)]
+pub(crate) struct Column {
+    pub column_name: String,
+}
+
+/// How the columns of the view differ from what [`TimeEntry`] expects.
+#[derive(Debug, Default, PartialEq)]
+pub struct ColumnDrift {
+    pub missing: Vec<String>,
+    pub unexpected: Vec<String>,
+}
+
+impl ColumnDrift {
+    pub fn is_empty(&self) -> bool {
+        self.missing.is_empty() && self.unexpected.is_empty()
+    }
+}
+
+pub(crate) fn create_statement(view: &str, sources: &SourceTables) -> String {
+    format!(
+        "create or replace materialized view {} as\n{}",
+        quote_identifier(view),
+        query(sources)
+    )
+}
+
+pub(crate) fn refresh_statement(view: &str) -> String {
+    format!("refresh materialized view {}", quote_identifier(view))
+}
+
+/// Lists the columns of `catalog.schema.view`.
+pub(crate) fn columns_query(catalog: &str, schema: &str, view: &str) -> Select {
+    Select::from(&format!("{catalog}.information_schema.columns"))
+        .columns(&["column_name"])
+        .filter_eq("table_schema", schema)
+        .filter_eq("table_name", view)
+        .order_by(&["ordinal_position"])
+}
+
+pub(crate) fn compare_columns(actual: &[Column]) -> ColumnDrift {
+    let actual: Vec<&str> = actual
+        .iter()
+        .map(|column| column.column_name.as_str())
+        .collect();
+
+    ColumnDrift {
+        missing: TimeEntry::COLUMNS
+            .iter()
+            .filter(|expected| !actual.contains(expected))
+            .map(|column| column.to_string())
+            .collect(),
+        unexpected: actual
+            .iter()
+            .filter(|column| !TimeEntry::COLUMNS.contains(column))
+            .map(|column| column.to_string())
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bdwh::config::SourceTables;
+    use crate::bdwh::structs::TimeEntry;
+    use crate::bdwh::view::{columns_query, compare_columns, create_statement, Column};
+
+    fn columns(names: &[&str]) -> Vec<Column> {
+        names
+            .iter()
+            .map(|name| Column {
+                column_name: name.to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_create_statement() {
+        let statement =
+            create_statement("lakehouse.bdwh.project_migration", &SourceTables::default());
+
+        assert!(statement.starts_with(
+            "create or replace materialized view \"lakehouse\".\"bdwh\".\"project_migration\" as\nselect"
+        ));
+        // Every column the entries are read into is provided by the view
+        for column in TimeEntry::COLUMNS {
+            assert!(
+                statement.contains(&format!(".{column}"))
+                    || statement.contains(&format!(" as {column}")),
+                "{column} is missing"
+            );
+        }
+    }
+
+    #[test]
+    fn test_create_statement_sources() {
+        let default = create_statement("project_migration", &SourceTables::default());
+        assert!(default.contains("\"gsheet\".\"default\".\"ban_cc\" b"));
+
+        let sources = SourceTables {
+            persons: "gsheet.staging.persons".to_string(),
+            ..SourceTables::default()
+        };
+        let statement = create_statement("project_migration", &sources);
+
+        assert!(statement.contains("\"lakehouse\".\"bdwh\".\"vw_stc_report_times_bill_l3y\" v"));
+        assert!(statement.contains("\"gsheet\".\"staging\".\"persons\" p"));
+    }
+
+    #[test]
+    fn test_columns_query() {
+        assert_eq!(
+            columns_query("lakehouse", "bdwh", "project_migration").to_string(),
+            "select column_name from \"lakehouse\".\"information_schema\".\"columns\" \
+            where \"table_schema\" = 'bdwh' and \"table_name\" = 'project_migration' \
+            order by ordinal_position"
+        );
+    }
+
+    #[test]
+    fn test_compare_columns() {
+        assert!(compare_columns(&columns(TimeEntry::COLUMNS)).is_empty());
+
+        let mut drifted: Vec<&str> = TimeEntry::COLUMNS
+            .iter()
+            .copied()
+            .filter(|column| *column != "cc_team")
+            .collect();
+        drifted.push("pma_person_code");
+        let drift = compare_columns(&columns(&drifted));
+
+        assert_eq!(drift.missing, vec!["cc_team"]);
+        assert_eq!(drift.unexpected, vec!["pma_person_code"]);
+    }
+}
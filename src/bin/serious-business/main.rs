@@ -0,0 +1,460 @@
+mod settings;
+
+use crate::settings::Settings;
+use clap::{Parser, Subcommand, ValueEnum};
+use serde::Serialize;
+use snafu::{ResultExt, Snafu};
+use stackable_serious_business::baserow::codegen::{self, Table};
+use stackable_serious_business::baserow::{self, client::Baserow};
+use stackable_serious_business::bdwh::structs::EntryFilter;
+use stackable_serious_business::bdwh::{self, Bdwh};
+use stackable_serious_business::coffeecup::client::{self, CoffeeCup};
+use stackable_serious_business::coffeecup::export::{ExportFormat, ExportRequest};
+use stackable_serious_business::coffeecup::resolver::Resolver;
+use stackable_serious_business::coffeecup::structs::Project;
+use stackable_serious_business::import::{self, CsvImport, PersonTable, ProjectMapping};
+use stackable_serious_business::migration::{self, Migrator};
+use stackable_serious_business::offers;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+#[derive(Snafu, Debug)]
+enum Error {
+    #[snafu(display("Failed to load settings: {source}"))]
+    Settings {
+        #[snafu(source(from(settings::Error, Box::new)))]
+        source: Box<settings::Error>,
+    },
+    #[snafu(display("Failed to connect to BDWH: {source}"))]
+    Bdwh { source: bdwh::Error },
+    #[snafu(display("Failed to talk to CoffeeCup: {source}"))]
+    CoffeeCup { source: client::Error },
+    #[snafu(display("Failed to import CSV: {source}"))]
+    Import { source: import::Error },
+    #[snafu(display("Migration failed: {source}"))]
+    Migration { source: migration::Error },
+    #[snafu(display("Failed to talk to Baserow: {source}"))]
+    Baserow { source: baserow::client::Error },
+    #[snafu(display("Failed to generate code: {source}"))]
+    Codegen { source: codegen::Error },
+    #[snafu(display("Failed to sync offers: {source}"))]
+    Offers { source: offers::Error },
+    #[snafu(display("Failed to write [{path}]: {source}"))]
+    WriteFile {
+        source: std::io::Error,
+        path: String,
+    },
+    #[snafu(display("Failed to render output as JSON: {source}"))]
+    RenderJson { source: serde_json::Error },
+}
+
+/// Routine tasks around BDWH and CoffeeCup.
+///
+/// Credentials are read from `serious-business.toml` in the current directory (or the file
+/// passed with --config) and can be overridden with environment variables such as
+/// COFFEECUP_USERNAME, COFFEECUP_PASSWORD, BDWH_HOST, BDWH_USERNAME, BDWH_PASSWORD,
+/// BASEROW_TOKEN and EASYBILL_API_KEY.
+#[derive(Parser, Debug)]
+#[command(name = "serious-business", version)]
+struct Cli {
+    /// Settings file to read credentials and endpoints from
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+
+    /// How results are printed
+    #[arg(long, global = true, value_enum, default_value_t = Output::Human)]
+    output: Output,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Output {
+    Human,
+    Json,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Copy all time entries booked on a BAN from BDWH to CoffeeCup
+    MigrateBan {
+        ban: String,
+
+        /// Only copy entries of this month, for example 2025-06
+        #[arg(long, value_parser = parse_month)]
+        month: Option<EntryFilter>,
+
+        /// Only show what would be created, changed and left over, without writing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Copy time entries from a CSV export of the legacy time tracking to CoffeeCup
+    ImportCsv {
+        file: PathBuf,
+
+        /// CSV file mapping person codes to CoffeeCup, with the columns code, cc_id and cc_team
+        #[arg(long)]
+        persons: PathBuf,
+
+        /// BAN the export belongs to
+        #[arg(long)]
+        ban: String,
+
+        /// CoffeeCup project to book on, by ID, code or name
+        #[arg(long)]
+        project: String,
+
+        /// CoffeeCup task to book on, by ID or label
+        #[arg(long)]
+        task: String,
+
+        /// Only show what would be created, changed and left over, without writing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Check that a person mapping refers to CoffeeCup users who are members of their teams
+    CheckPersons {
+        /// CSV file mapping person codes to CoffeeCup, with the columns code, cc_id and cc_team
+        persons: PathBuf,
+    },
+    /// Download the CoffeeCup hour overview of a month
+    ExportHours {
+        /// Month to export, for example 2025-06
+        #[arg(value_parser = parse_export_month)]
+        month: ExportRequest,
+
+        /// File format, pdf, csv or xlsx
+        #[arg(long, default_value = "pdf")]
+        format: ExportFormat,
+
+        /// File to write the export to
+        #[arg(long, short)]
+        file: PathBuf,
+    },
+    /// Manage the BDWH view time entries are migrated from
+    MigrationView {
+        #[command(subcommand)]
+        action: ViewAction,
+    },
+    /// List all CoffeeCup projects
+    ListProjects,
+    /// List all CoffeeCup tags
+    ListTags,
+    /// List the CoffeeCup projects that carry a tag
+    ProjectsByTag { tag: String },
+    /// Keep the Baserow offers table in line with easybill
+    Offers {
+        #[command(subcommand)]
+        action: OffersAction,
+    },
+    /// Work with the structure of Baserow tables
+    Baserow {
+        #[command(subcommand)]
+        action: BaserowAction,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum ViewAction {
+    /// Create the view, or replace it if it exists
+    Create,
+    /// Update the view with the current BDWH data
+    Refresh,
+    /// Check that the view has the expected columns
+    Check,
+}
+
+#[derive(Subcommand, Debug)]
+enum OffersAction {
+    /// Create and update rows of the Baserow offers table from the offers in easybill
+    Sync {
+        /// Only show what would be created and updated, without writing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum BaserowAction {
+    /// Generate Rust types for Baserow tables from their fields
+    Codegen {
+        /// Table to generate a struct for, as struct name and table ID, for example
+        /// Offers=568215
+        #[arg(long = "table", required = true, value_parser = parse_table)]
+        tables: Vec<(String, usize)>,
+
+        /// File to write the code to, it is printed if unset
+        #[arg(long, short)]
+        file: Option<PathBuf>,
+    },
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    match run(cli).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("{}", snafu::Report::from_error(error));
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(cli: Cli) -> Result<(), Error> {
+    let settings = Settings::load(cli.config.as_deref()).context(SettingsSnafu)?;
+    match &cli.command {
+        Command::MigrationView { action } => {
+            let bdwh = Bdwh::new(&settings.bdwh).context(BdwhSnafu)?;
+            match action {
+                ViewAction::Create => bdwh.create_migration_view().await,
+                ViewAction::Refresh => bdwh.refresh_migration_view().await,
+                ViewAction::Check => bdwh.check_migration_view().await,
+            }
+            .context(BdwhSnafu)?;
+            return Ok(());
+        }
+        Command::Offers {
+            action: OffersAction::Sync { dry_run },
+        } => {
+            let easybill = settings.easybill.client().context(SettingsSnafu)?;
+            let baserow = Baserow::new(settings.baserow.config().context(SettingsSnafu)?)
+                .context(BaserowSnafu)?;
+            let report = offers::sync_offers(&easybill, &baserow, *dry_run)
+                .await
+                .context(OffersSnafu)?;
+            return print(cli.output, &report, || report.to_string());
+        }
+        Command::Baserow {
+            action: BaserowAction::Codegen { tables, file },
+        } => {
+            let baserow = Baserow::new(settings.baserow.config().context(SettingsSnafu)?)
+                .context(BaserowSnafu)?;
+            let mut with_fields = Vec::new();
+            for (name, id) in tables {
+                with_fields.push(Table {
+                    name: name.clone(),
+                    id: *id,
+                    fields: baserow.list_fields(*id).await.context(BaserowSnafu)?,
+                });
+            }
+            let code = codegen::generate(&with_fields).context(CodegenSnafu)?;
+            match file {
+                Some(file) => std::fs::write(file, code).context(WriteFileSnafu {
+                    path: file.display().to_string(),
+                })?,
+                None => print!("{code}"),
+            }
+            return Ok(());
+        }
+        _ => {}
+    }
+
+    let coffeecup = CoffeeCup::new_with_config(
+        settings.coffeecup.config().context(SettingsSnafu)?,
+        settings.coffeecup.credentials().context(SettingsSnafu)?,
+        settings.coffeecup.token_cache.clone(),
+    )
+    .await
+    .context(CoffeeCupSnafu)?;
+
+    match cli.command {
+        Command::MigrateBan {
+            ban,
+            month,
+            dry_run,
+        } => {
+            let bdwh = Bdwh::new(&settings.bdwh).context(BdwhSnafu)?;
+            bdwh.check_migration_view().await.context(BdwhSnafu)?;
+            let migrator =
+                Migrator::new(&bdwh, &coffeecup).with_policy(settings.conversion.clone());
+            let filter = month.unwrap_or_default().ban(ban);
+
+            if dry_run {
+                let plan = migrator.plan(&filter).await.context(MigrationSnafu)?;
+                print(cli.output, &plan, || plan.to_string())
+            } else {
+                let report = migrator.migrate(&filter).await.context(MigrationSnafu)?;
+                print(cli.output, &report, || report.to_string())
+            }
+        }
+        Command::ImportCsv {
+            file,
+            persons,
+            ban,
+            project,
+            task,
+            dry_run,
+        } => {
+            let persons = PersonTable::from_csv_file(&persons).context(ImportSnafu)?;
+            let import = CsvImport::new(
+                persons,
+                ProjectMapping {
+                    ban,
+                    cc_project: project,
+                    cc_task: task,
+                },
+            );
+            let entries = import.read_file(&file).context(ImportSnafu)?;
+            let resolver = Resolver::load(&coffeecup).await.context(CoffeeCupSnafu)?;
+
+            if dry_run {
+                let plan =
+                    migration::plan_entries(&coffeecup, &resolver, &settings.conversion, entries)
+                        .await
+                        .context(MigrationSnafu)?;
+                print(cli.output, &plan, || plan.to_string())
+            } else {
+                let report = migration::migrate_entries(
+                    &coffeecup,
+                    &resolver,
+                    &settings.conversion,
+                    entries,
+                )
+                .await
+                .context(MigrationSnafu)?;
+                print(cli.output, &report, || report.to_string())
+            }
+        }
+        Command::CheckPersons { persons } => {
+            let persons = PersonTable::from_csv_file(&persons).context(ImportSnafu)?;
+            let (resolver, memberships) = tokio::try_join!(
+                Resolver::load(&coffeecup),
+                coffeecup.list_team_memberships()
+            )
+            .context(CoffeeCupSnafu)?;
+
+            let checks: Vec<PersonCheck> = persons
+                .persons()
+                .into_iter()
+                .map(|person| PersonCheck {
+                    code: person.code.clone(),
+                    problem: resolver
+                        .check_person(&person.code, &person.cc_id, &person.cc_team, &memberships)
+                        .err()
+                        .map(|error| error.to_string()),
+                })
+                .collect();
+            print(cli.output, &checks, || {
+                checks
+                    .iter()
+                    .map(|check| {
+                        format!(
+                            "{:<8}  {}",
+                            check.code,
+                            check.problem.as_deref().unwrap_or("ok")
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            })
+        }
+        Command::ExportHours {
+            month,
+            format,
+            file,
+        } => {
+            let export = coffeecup
+                .export_timeentries(&month.format(format))
+                .await
+                .context(CoffeeCupSnafu)?;
+            std::fs::write(&file, export).context(WriteFileSnafu {
+                path: file.display().to_string(),
+            })
+        }
+        // Handled above, as they do not need CoffeeCup
+        Command::MigrationView { .. } | Command::Offers { .. } | Command::Baserow { .. } => Ok(()),
+        Command::ListProjects => {
+            let projects = coffeecup.list_projects().await.context(CoffeeCupSnafu)?;
+            print(cli.output, &projects, || project_table(&projects))
+        }
+        Command::ListTags => {
+            let tags = coffeecup.list_tags().await.context(CoffeeCupSnafu)?;
+            print(cli.output, &tags, || {
+                tags.iter()
+                    .map(|tag| format!("{:>8}  {}", tag.id, tag.label))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            })
+        }
+        Command::ProjectsByTag { tag } => {
+            let project_ids = coffeecup
+                .get_project_ids_by_tag(&tag)
+                .await
+                .context(CoffeeCupSnafu)?;
+            let projects: Vec<Project> = coffeecup
+                .list_projects()
+                .await
+                .context(CoffeeCupSnafu)?
+                .into_iter()
+                .filter(|project| project_ids.contains(&project.id))
+                .collect();
+            print(cli.output, &projects, || project_table(&projects))
+        }
+    }
+}
+
+/// Outcome of checking the mapping of a single person.
+#[derive(Debug, Serialize)]
+struct PersonCheck {
+    code: String,
+    /// Unset if the person maps to a user who is a member of their team
+    problem: Option<String>,
+}
+
+/// Parses `2025-06` into a filter for June 2025.
+fn parse_month(value: &str) -> Result<EntryFilter, String> {
+    parse_year_month(value, EntryFilter::month)
+}
+
+/// Parses `2025-06` into an export of June 2025.
+fn parse_export_month(value: &str) -> Result<ExportRequest, String> {
+    parse_year_month(value, ExportRequest::month)
+}
+
+/// Parses `Offers=568215` into the struct name and ID of a table.
+fn parse_table(value: &str) -> Result<(String, usize), String> {
+    value
+        .split_once('=')
+        .and_then(|(name, id)| Some((name.to_string(), id.parse().ok()?)))
+        .ok_or_else(|| format!("expected a table like Offers=568215, got [{value}]"))
+}
+
+fn parse_year_month<T>(value: &str, month: fn(i32, u32) -> Option<T>) -> Result<T, String> {
+    value
+        .split_once('-')
+        .and_then(|(year, number)| month(year.parse().ok()?, number.parse().ok()?))
+        .ok_or_else(|| format!("expected a month like 2025-06, got [{value}]"))
+}
+
+/// Prints `value` as JSON, or as rendered by `human` for people.
+fn print<T>(output: Output, value: &T, human: impl FnOnce() -> String) -> Result<(), Error>
+where
+    T: Serialize,
+{
+    match output {
+        Output::Human => println!("{}", human()),
+        Output::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(value).context(RenderJsonSnafu)?
+        ),
+    }
+    Ok(())
+}
+
+fn project_table(projects: &[Project]) -> String {
+    projects
+        .iter()
+        .map(|project| {
+            format!(
+                "{:>8}  {:<10}  {}",
+                project.id,
+                project.code.as_deref().unwrap_or_default(),
+                project.name
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -0,0 +1,218 @@
+use serde::Deserialize;
+use snafu::{OptionExt, ResultExt, Snafu};
+use stackable_serious_business::baserow::{self, config::BaserowConfig};
+use stackable_serious_business::bdwh::{self, config::BdwhConfig};
+use stackable_serious_business::coffeecup::auth::Credentials;
+use stackable_serious_business::coffeecup::client;
+use stackable_serious_business::coffeecup::config::CoffeeCupConfig;
+use stackable_serious_business::coffeecup::conversion::ConversionPolicy;
+use stackable_serious_business::easybill::client::{self as easybill, Easybill};
+use std::path::{Path, PathBuf};
+
+/// Used if no settings file is passed on the command line.
+const DEFAULT_SETTINGS_FILE: &str = "serious-business.toml";
+
+#[derive(Snafu, Debug)]
+pub enum Error {
+    #[snafu(display("Failed to read settings file [{path}]: {source}"))]
+    ReadSettings {
+        source: std::io::Error,
+        path: String,
+    },
+    #[snafu(display("Failed to parse settings file [{path}]: {source}"))]
+    ParseSettings {
+        source: toml::de::Error,
+        path: String,
+    },
+    #[snafu(display("Missing setting [{name}], set it in the settings file or as [{env}]"))]
+    MissingSetting { name: String, env: String },
+    #[snafu(display("Invalid CoffeeCup url: {source}"))]
+    CoffeeCupUrl { source: client::Error },
+    #[snafu(display("Invalid BDWH settings: {source}"))]
+    Bdwh { source: bdwh::Error },
+    #[snafu(display("Invalid Baserow url: {source}"))]
+    BaserowUrl { source: baserow::client::Error },
+    #[snafu(display("Invalid easybill url: {source}"))]
+    EasybillUrl { source: easybill::Error },
+}
+
+/// Credentials and endpoints, read from a TOML file and overridden by environment variables.
+///
+/// ```toml
+/// [coffeecup]
+/// username = "jane.doe@stackable.tech"
+/// password = "..."
+/// token_cache = "/home/jane/.cache/serious-business-token.json"
+///
+/// [bdwh]
+/// username = "jane"
+/// auth = { method = "basic", password = "..." }
+///
+/// [conversion]
+/// billing = "split"
+/// rounding = { mode = "nearest", minutes = 15 }
+///
+/// [baserow]
+/// token = "..."
+///
+/// [easybill]
+/// api_key = "..."
+/// ```
+///
+/// See [`BdwhConfig`] for all BDWH settings and [`ConversionPolicy`] for how entries are
+/// converted.
+#[derive(Debug, Default, Deserialize)]
+pub struct Settings {
+    #[serde(default)]
+    pub coffeecup: CoffeeCupSettings,
+    #[serde(default)]
+    pub bdwh: BdwhConfig,
+    #[serde(default)]
+    pub conversion: ConversionPolicy,
+    #[serde(default)]
+    pub baserow: BaserowSettings,
+    #[serde(default)]
+    pub easybill: EasybillSettings,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct CoffeeCupSettings {
+    pub base_url: Option<String>,
+    pub company_url: Option<String>,
+    /// Logs in with the password grant if set, with client credentials otherwise
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+    pub token_cache: Option<PathBuf>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct BaserowSettings {
+    /// Hosted Baserow if unset
+    pub url: Option<String>,
+    /// Database token with access to the tables that are read and written
+    pub token: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct EasybillSettings {
+    pub url: Option<String>,
+    pub api_key: Option<String>,
+}
+
+impl Settings {
+    /// Reads `path`, or the default settings file if it exists, and applies environment
+    /// overrides on top.
+    pub fn load(path: Option<&Path>) -> Result<Self, Error> {
+        let path = path
+            .map(Path::to_path_buf)
+            .or_else(|| Some(PathBuf::from(DEFAULT_SETTINGS_FILE)).filter(|path| path.exists()));
+
+        let mut settings = match path {
+            Some(path) => {
+                let content = std::fs::read_to_string(&path).context(ReadSettingsSnafu {
+                    path: path.display().to_string(),
+                })?;
+                toml::from_str(&content).context(ParseSettingsSnafu {
+                    path: path.display().to_string(),
+                })?
+            }
+            None => Settings::default(),
+        };
+
+        let coffeecup = &mut settings.coffeecup;
+        override_from_env(&mut coffeecup.base_url, "COFFEECUP_BASE_URL");
+        override_from_env(&mut coffeecup.company_url, "COFFEECUP_COMPANY_URL");
+        override_from_env(&mut coffeecup.username, "COFFEECUP_USERNAME");
+        override_from_env(&mut coffeecup.password, "COFFEECUP_PASSWORD");
+        override_from_env(&mut coffeecup.client_id, "COFFEECUP_CLIENT_ID");
+        override_from_env(&mut coffeecup.client_secret, "COFFEECUP_CLIENT_SECRET");
+        if let Ok(token_cache) = std::env::var("COFFEECUP_TOKEN_CACHE") {
+            coffeecup.token_cache = Some(PathBuf::from(token_cache));
+        }
+        settings.bdwh = settings.bdwh.with_env_overrides().context(BdwhSnafu)?;
+        override_from_env(&mut settings.baserow.url, "BASEROW_URL");
+        override_from_env(&mut settings.baserow.token, "BASEROW_TOKEN");
+        override_from_env(&mut settings.easybill.url, "EASYBILL_URL");
+        override_from_env(&mut settings.easybill.api_key, "EASYBILL_API_KEY");
+
+        Ok(settings)
+    }
+}
+
+impl CoffeeCupSettings {
+    pub fn config(&self) -> Result<CoffeeCupConfig, Error> {
+        let default = CoffeeCupConfig::default();
+        match (&self.base_url, &self.company_url) {
+            (None, None) => Ok(default),
+            (base_url, company_url) => CoffeeCupConfig::new(
+                base_url.as_deref().unwrap_or(default.base_url.as_str()),
+                company_url
+                    .as_deref()
+                    .unwrap_or(default.company_url.as_str()),
+            )
+            .context(CoffeeCupUrlSnafu),
+        }
+    }
+
+    pub fn credentials(&self) -> Result<Credentials, Error> {
+        let mut credentials = match &self.username {
+            Some(username) => Credentials::password(
+                username,
+                require(&self.password, "coffeecup.password", "COFFEECUP_PASSWORD")?,
+            ),
+            None => Credentials::client_credentials(
+                require(
+                    &self.client_id,
+                    "coffeecup.client_id",
+                    "COFFEECUP_CLIENT_ID",
+                )?,
+                require(
+                    &self.client_secret,
+                    "coffeecup.client_secret",
+                    "COFFEECUP_CLIENT_SECRET",
+                )?,
+            ),
+        };
+        if let Some(client_id) = &self.client_id {
+            credentials.client_id = client_id.clone();
+        }
+        if let Some(client_secret) = &self.client_secret {
+            credentials.client_secret = client_secret.clone();
+        }
+        Ok(credentials)
+    }
+}
+
+impl BaserowSettings {
+    pub fn config(&self) -> Result<BaserowConfig, Error> {
+        let token = require(&self.token, "baserow.token", "BASEROW_TOKEN")?;
+        match &self.url {
+            Some(url) => BaserowConfig::new(url, token).context(BaserowUrlSnafu),
+            None => Ok(BaserowConfig::hosted(token)),
+        }
+    }
+}
+
+impl EasybillSettings {
+    pub fn client(&self) -> Result<Easybill, Error> {
+        Easybill::new(
+            self.url.as_deref().unwrap_or(easybill::DEFAULT_URL),
+            require(&self.api_key, "easybill.api_key", "EASYBILL_API_KEY")?,
+        )
+        .context(EasybillUrlSnafu)
+    }
+}
+
+fn override_from_env(setting: &mut Option<String>, env: &str) {
+    if let Ok(value) = std::env::var(env) {
+        *setting = Some(value);
+    }
+}
+
+fn require<'a>(setting: &'a Option<String>, name: &str, env: &str) -> Result<&'a str, Error> {
+    setting
+        .as_deref()
+        .context(MissingSettingSnafu { name, env })
+}
@@ -0,0 +1,395 @@
+use crate::coffeecup::client::{
+    Error, ReadTokenCacheSnafu, RequestTokenSnafu, SerializeRequestSnafu, WriteTokenCacheSnafu,
+};
+use crate::coffeecup::config::CoffeeCupConfig;
+use chrono::{DateTime, TimeDelta, Utc};
+use oauth2::basic::{BasicClient, BasicTokenResponse};
+use oauth2::{
+    ClientId, ClientSecret, EndpointNotSet, EndpointSet, RefreshToken, ResourceOwnerPassword,
+    ResourceOwnerUsername, TokenResponse, TokenUrl,
+};
+use serde::{Deserialize, Serialize};
+use snafu::ResultExt;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tokio::sync::Mutex;
+
+type OAuthClient =
+    BasicClient<EndpointNotSet, EndpointNotSet, EndpointNotSet, EndpointNotSet, EndpointSet>;
+
+/// Tokens that expire within this margin are refreshed before they are used.
+const REFRESH_MARGIN: TimeDelta = TimeDelta::minutes(5);
+
+/// The OAuth2 grant used to log in to CoffeeCup.
+#[derive(Debug, Clone)]
+pub enum Grant {
+    ClientCredentials,
+    Password { username: String, password: String },
+}
+
+/// Everything needed to obtain a token from the CoffeeCup tenant.
+#[derive(Debug, Clone)]
+pub struct Credentials {
+    pub client_id: String,
+    pub client_secret: String,
+    pub grant: Grant,
+}
+
+impl Credentials {
+    /// Logs in as a user, with the client id CoffeeCup's own apps use.
+    pub fn password(username: &str, password: &str) -> Self {
+        Self {
+            client_id: "client_id".to_string(),
+            client_secret: "client_secret".to_string(),
+            grant: Grant::Password {
+                username: username.to_string(),
+                password: password.to_string(),
+            },
+        }
+    }
+
+    /// The user tokens are issued to, `None` for client credentials.
+    pub fn username(&self) -> Option<&str> {
+        match &self.grant {
+            Grant::ClientCredentials => None,
+            Grant::Password { username, .. } => Some(username),
+        }
+    }
+
+    /// Logs in as an API client registered with the tenant.
+    pub fn client_credentials(client_id: &str, client_secret: &str) -> Self {
+        Self {
+            client_id: client_id.to_string(),
+            client_secret: client_secret.to_string(),
+            grant: Grant::ClientCredentials,
+        }
+    }
+}
+
+/// An access token together with the absolute point in time it expires at, and the login it
+/// has been issued to.
+///
+/// This is also the format tokens are persisted in when a cache file is configured.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CachedToken {
+    pub access_token: String,
+    pub refresh_token: Option<String>,
+    /// `None` if the server did not say when the token expires
+    pub expires_at: Option<DateTime<Utc>>,
+    pub token_url: String,
+    pub client_id: String,
+    /// `None` for tokens obtained with client credentials
+    pub username: Option<String>,
+}
+
+impl CachedToken {
+    /// Returns true if the token has been issued to the same tenant, client and user as tokens
+    /// requested with `credentials` from `token_url`.
+    pub fn issued_to(&self, token_url: &str, credentials: &Credentials) -> bool {
+        self.token_url == token_url
+            && self.client_id == credentials.client_id
+            && self.username.as_deref() == credentials.username()
+    }
+    /// Returns true if the token should not be used anymore at `now`.
+    pub fn needs_refresh(&self, now: DateTime<Utc>) -> bool {
+        match self.expires_at {
+            Some(expires_at) => expires_at - REFRESH_MARGIN <= now,
+            // We can't know, so keep using it until the server rejects it
+            None => false,
+        }
+    }
+}
+
+/// Hands out valid access tokens for the CoffeeCup API.
+///
+/// Tokens are refreshed shortly before they expire, or when the API rejects them, and are
+/// optionally written to a cache file so that subsequent runs don't need to log in again.
+pub struct TokenManager {
+    oauth_client: OAuthClient,
+    http_client: reqwest::Client,
+    credentials: Credentials,
+    token_url: String,
+    cache_file: Option<PathBuf>,
+    token: Mutex<Option<CachedToken>>,
+}
+
+impl TokenManager {
+    pub fn new(
+        config: &CoffeeCupConfig,
+        credentials: Credentials,
+        http_client: reqwest::Client,
+    ) -> Result<Self, Error> {
+        let token_url = config.token_url()?;
+        let oauth_client = BasicClient::new(ClientId::new(credentials.client_id.clone()))
+            .set_client_secret(ClientSecret::new(credentials.client_secret.clone()))
+            .set_token_uri(TokenUrl::from_url(token_url.clone()));
+
+        Ok(Self {
+            oauth_client,
+            http_client,
+            credentials,
+            token_url: token_url.to_string(),
+            cache_file: None,
+            token: Mutex::new(None),
+        })
+    }
+
+    /// Persists tokens to `path` and picks up a token stored there by an earlier run.
+    pub fn with_cache_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.cache_file = Some(path.into());
+        self
+    }
+
+    /// Returns a token that is valid for at least a few more minutes.
+    ///
+    /// A cached token that has been issued to another tenant, client or user, for example
+    /// because the configuration changed since it was written, is ignored.
+    pub async fn access_token(&self) -> Result<String, Error> {
+        let mut token = self.token.lock().await;
+
+        if token.is_none() {
+            if let Some(cache_file) = &self.cache_file {
+                *token = read_cache(cache_file)?
+                    .filter(|cached| cached.issued_to(&self.token_url, &self.credentials));
+            }
+        }
+
+        let current = match token.take() {
+            Some(current) if !current.needs_refresh(Utc::now()) => current,
+            Some(expiring) => self.refresh(&expiring).await?,
+            None => self.login().await?,
+        };
+        let access_token = current.access_token.clone();
+        *token = Some(current);
+        Ok(access_token)
+    }
+
+    /// Replaces `rejected` after the API refused it, and returns the new token.
+    ///
+    /// If another request has already replaced the token in the meantime, that token is
+    /// returned instead of refreshing a second time.
+    pub async fn refresh_rejected(&self, rejected: &str) -> Result<String, Error> {
+        let mut token = self.token.lock().await;
+
+        let current = match token.take() {
+            Some(current) if current.access_token != rejected => current,
+            Some(current) => self.refresh(&current).await?,
+            None => self.login().await?,
+        };
+        let access_token = current.access_token.clone();
+        *token = Some(current);
+        Ok(access_token)
+    }
+
+    async fn login(&self) -> Result<CachedToken, Error> {
+        let issued_at = Utc::now();
+        let response = match &self.credentials.grant {
+            Grant::ClientCredentials => {
+                self.oauth_client
+                    .exchange_client_credentials()
+                    .request_async(&self.http_client)
+                    .await
+            }
+            Grant::Password { username, password } => {
+                self.oauth_client
+                    .exchange_password(
+                        &ResourceOwnerUsername::new(username.clone()),
+                        &ResourceOwnerPassword::new(password.clone()),
+                    )
+                    .request_async(&self.http_client)
+                    .await
+            }
+        }
+        .context(RequestTokenSnafu {
+            url: &self.token_url,
+        })?;
+
+        self.store(self.token_from(&response, issued_at))
+    }
+
+    /// Uses the refresh token if there is one, and falls back to logging in again if there
+    /// is none or the server won't accept it anymore.
+    async fn refresh(&self, token: &CachedToken) -> Result<CachedToken, Error> {
+        let Some(refresh_token) = &token.refresh_token else {
+            return self.login().await;
+        };
+
+        let issued_at = Utc::now();
+        match self
+            .oauth_client
+            .exchange_refresh_token(&RefreshToken::new(refresh_token.clone()))
+            .request_async(&self.http_client)
+            .await
+        {
+            Ok(response) => {
+                let mut refreshed = self.token_from(&response, issued_at);
+                // Servers may keep the refresh token and not send it again
+                if refreshed.refresh_token.is_none() {
+                    refreshed.refresh_token = token.refresh_token.clone();
+                }
+                self.store(refreshed)
+            }
+            Err(_) => self.login().await,
+        }
+    }
+
+    fn token_from(&self, response: &BasicTokenResponse, issued_at: DateTime<Utc>) -> CachedToken {
+        CachedToken {
+            access_token: response.access_token().secret().clone(),
+            refresh_token: response
+                .refresh_token()
+                .map(|refresh_token| refresh_token.secret().clone()),
+            expires_at: response
+                .expires_in()
+                .and_then(|expires_in| TimeDelta::from_std(expires_in).ok())
+                .map(|expires_in| issued_at + expires_in),
+            token_url: self.token_url.clone(),
+            client_id: self.credentials.client_id.clone(),
+            username: self.credentials.username().map(str::to_string),
+        }
+    }
+
+    fn store(&self, token: CachedToken) -> Result<CachedToken, Error> {
+        if let Some(cache_file) = &self.cache_file {
+            write_cache(cache_file, &token)?;
+        }
+        Ok(token)
+    }
+}
+
+/// Reads a token persisted by [`write_cache`].
+///
+/// A missing file is not an error, and neither is one that can't be parsed: that token is
+/// useless either way, so the caller logs in again and overwrites it.
+pub fn read_cache(path: &Path) -> Result<Option<CachedToken>, Error> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = std::fs::read_to_string(path).context(ReadTokenCacheSnafu {
+        path: path.display().to_string(),
+    })?;
+    Ok(serde_json::from_str(&content).ok())
+}
+
+pub fn write_cache(path: &Path, token: &CachedToken) -> Result<(), Error> {
+    let content = serde_json::to_string_pretty(token)
+        .context(SerializeRequestSnafu { msg: "token cache" })?;
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    // The file grants access to CoffeeCup, so keep other users from reading it
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path).context(WriteTokenCacheSnafu {
+        path: path.display().to_string(),
+    })?;
+    // The mode only applies to new files, a cache left behind by an older version may be
+    // readable by everyone
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))
+            .context(WriteTokenCacheSnafu {
+                path: path.display().to_string(),
+            })?;
+    }
+    file.write_all(content.as_bytes())
+        .context(WriteTokenCacheSnafu {
+            path: path.display().to_string(),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::coffeecup::auth::{read_cache, write_cache, CachedToken, Credentials};
+    use chrono::{TimeDelta, TimeZone, Utc};
+
+    fn token(expires_at: Option<chrono::DateTime<Utc>>) -> CachedToken {
+        CachedToken {
+            access_token: "access".to_string(),
+            refresh_token: Some("refresh".to_string()),
+            expires_at,
+            token_url: "https://stackable.coffeecup.app/oauth2/token".to_string(),
+            client_id: "client_id".to_string(),
+            username: Some("user".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_needs_refresh() {
+        let now = Utc.with_ymd_and_hms(2025, 6, 2, 12, 0, 0).unwrap();
+
+        assert!(!token(Some(now + TimeDelta::hours(1))).needs_refresh(now));
+        // Refreshed shortly before it actually expires
+        assert!(token(Some(now + TimeDelta::minutes(2))).needs_refresh(now));
+        assert!(token(Some(now - TimeDelta::minutes(1))).needs_refresh(now));
+        assert!(!token(None).needs_refresh(now));
+    }
+
+    #[test]
+    fn test_issued_to() {
+        let token_url = "https://stackable.coffeecup.app/oauth2/token";
+        let cached = token(None);
+
+        assert!(cached.issued_to(token_url, &Credentials::password("user", "secret")));
+        assert!(!cached.issued_to(token_url, &Credentials::password("other", "secret")));
+        assert!(!cached.issued_to(
+            "https://other.coffeecup.app/oauth2/token",
+            &Credentials::password("user", "secret")
+        ));
+        assert!(!cached.issued_to(
+            token_url,
+            &Credentials::client_credentials("client_id", "secret")
+        ));
+    }
+
+    #[test]
+    fn test_cache_roundtrip() {
+        let path =
+            std::env::temp_dir().join(format!("coffeecup-token-cache-{}.json", std::process::id()));
+        let cached = token(Some(Utc.with_ymd_and_hms(2025, 6, 2, 12, 0, 0).unwrap()));
+
+        write_cache(&path, &cached).unwrap();
+        let restored = read_cache(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(restored, Some(cached));
+        assert_eq!(read_cache(&path).unwrap(), None);
+    }
+
+    #[test]
+    fn test_corrupt_cache() {
+        let path = std::env::temp_dir().join(format!(
+            "coffeecup-token-cache-corrupt-{}.json",
+            std::process::id()
+        ));
+        std::fs::write(&path, "{\"access_token\": ").unwrap();
+
+        let restored = read_cache(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(restored, None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_cache_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = std::env::temp_dir().join(format!(
+            "coffeecup-token-cache-mode-{}.json",
+            std::process::id()
+        ));
+        std::fs::write(&path, "").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+        write_cache(&path, &token(None)).unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(mode & 0o777, 0o600);
+    }
+}
@@ -0,0 +1,225 @@
+//! All CoffeeCup projects together with their tasks, customer and tags, indexed for lookups.
+
+use crate::coffeecup::resolver::normalize;
+use crate::coffeecup::structs::{
+    Customer, Project, Tag, TagAssignment, TagModel, Task, TaskAssignment,
+};
+use std::collections::HashMap;
+
+/// A project with everything that is attached to it.
+#[derive(Debug, Clone)]
+pub struct CatalogEntry {
+    /// The project, with `tasks` set to the tasks assigned to it
+    pub project: Project,
+    pub customer: Option<Customer>,
+    pub tags: Vec<Tag>,
+}
+
+/// Projects that can be looked up by name, code, client or tag, see
+/// [`crate::coffeecup::client::CoffeeCup::get_my_projects`].
+///
+/// Names, codes and tag labels are matched case-insensitively.
+#[derive(Debug, Clone, Default)]
+pub struct ProjectCatalog {
+    entries: Vec<CatalogEntry>,
+    by_id: HashMap<usize, usize>,
+    by_name: HashMap<String, Vec<usize>>,
+    by_code: HashMap<String, Vec<usize>>,
+    by_client: HashMap<usize, Vec<usize>>,
+    by_tag: HashMap<String, Vec<usize>>,
+}
+
+impl ProjectCatalog {
+    /// Joins `projects` with the other lists, keeping the order of `projects`.
+    ///
+    /// Tag assignments to anything but projects are ignored.
+    pub fn new(
+        projects: Vec<Project>,
+        tasks: &[Task],
+        task_assignments: &[TaskAssignment],
+        customers: &[Customer],
+        tags: &[Tag],
+        tag_assignments: &[TagAssignment],
+    ) -> Self {
+        let tasks: HashMap<usize, &Task> = tasks.iter().map(|task| (task.id, task)).collect();
+        let customers: HashMap<usize, &Customer> = customers
+            .iter()
+            .map(|customer| (customer.id, customer))
+            .collect();
+        let tags: HashMap<usize, &Tag> = tags.iter().map(|tag| (tag.id, tag)).collect();
+
+        let mut project_tasks: HashMap<usize, Vec<Task>> = HashMap::new();
+        for assignment in task_assignments {
+            if let Some(task) = tasks.get(&assignment.task) {
+                project_tasks
+                    .entry(assignment.project)
+                    .or_default()
+                    .push((*task).clone());
+            }
+        }
+        let mut project_tags: HashMap<usize, Vec<Tag>> = HashMap::new();
+        for assignment in tag_assignments {
+            if assignment.model != TagModel::Project {
+                continue;
+            }
+            if let Some(tag) = tags.get(&assignment.tag) {
+                project_tags
+                    .entry(assignment.record)
+                    .or_default()
+                    .push((*tag).clone());
+            }
+        }
+
+        let mut catalog = Self::default();
+        for mut project in projects {
+            project.tasks = Some(project_tasks.remove(&project.id).unwrap_or_default());
+            let customer = project
+                .client
+                .and_then(|client| customers.get(&client))
+                .map(|customer| (*customer).clone());
+            let tags = project_tags.remove(&project.id).unwrap_or_default();
+            catalog.insert(CatalogEntry {
+                project,
+                customer,
+                tags,
+            });
+        }
+        catalog
+    }
+
+    fn insert(&mut self, entry: CatalogEntry) {
+        let index = self.entries.len();
+        let project = &entry.project;
+        self.by_id.insert(project.id, index);
+        self.by_name
+            .entry(normalize(&project.name))
+            .or_default()
+            .push(index);
+        if let Some(code) = &project.code {
+            self.by_code.entry(normalize(code)).or_default().push(index);
+        }
+        if let Some(client) = project.client {
+            self.by_client.entry(client).or_default().push(index);
+        }
+        for tag in &entry.tags {
+            self.by_tag
+                .entry(normalize(&tag.label))
+                .or_default()
+                .push(index);
+        }
+        self.entries.push(entry);
+    }
+
+    pub fn entries(&self) -> &[CatalogEntry] {
+        &self.entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the projects, with their tasks set.
+    pub fn into_projects(self) -> Vec<Project> {
+        self.entries
+            .into_iter()
+            .map(|entry| entry.project)
+            .collect()
+    }
+
+    pub fn get(&self, id: usize) -> Option<&CatalogEntry> {
+        self.by_id.get(&id).map(|index| &self.entries[*index])
+    }
+
+    pub fn by_name(&self, name: &str) -> Vec<&CatalogEntry> {
+        self.lookup(&self.by_name, &normalize(name))
+    }
+
+    pub fn by_code(&self, code: &str) -> Vec<&CatalogEntry> {
+        self.lookup(&self.by_code, &normalize(code))
+    }
+
+    /// Returns the projects done for the client with the ID `client`.
+    pub fn by_client(&self, client: usize) -> Vec<&CatalogEntry> {
+        self.lookup(&self.by_client, &client)
+    }
+
+    /// Returns the projects carrying the tag with the label `label`.
+    pub fn by_tag(&self, label: &str) -> Vec<&CatalogEntry> {
+        self.lookup(&self.by_tag, &normalize(label))
+    }
+
+    fn lookup<K>(&self, index: &HashMap<K, Vec<usize>>, key: &K) -> Vec<&CatalogEntry>
+    where
+        K: Eq + std::hash::Hash,
+    {
+        index
+            .get(key)
+            .map(|indices| indices.iter().map(|index| &self.entries[*index]).collect())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::coffeecup::catalog::{CatalogEntry, ProjectCatalog};
+    use crate::coffeecup::mock::parse_list;
+    use crate::coffeecup::structs::Project;
+
+    fn catalog() -> ProjectCatalog {
+        let mut projects: Vec<Project> = parse_list("list_projects_page1.json");
+        projects.extend(parse_list::<Project>("list_projects_page2.json"));
+        ProjectCatalog::new(
+            projects,
+            &parse_list("list_tasks.json"),
+            &parse_list("list_taskassignments.json"),
+            &parse_list("list_clients.json"),
+            &parse_list("list_tags.json"),
+            &parse_list("list_tagassignments.json"),
+        )
+    }
+
+    fn ids(entries: Vec<&CatalogEntry>) -> Vec<usize> {
+        entries.iter().map(|entry| entry.project.id).collect()
+    }
+
+    #[test]
+    fn test_join() {
+        let catalog = catalog();
+
+        assert_eq!(catalog.len(), 3);
+        let kafka = catalog.get(10).unwrap();
+        let task_labels: Vec<&str> = kafka
+            .project
+            .tasks
+            .as_ref()
+            .unwrap()
+            .iter()
+            .map(|task| task.label.as_str())
+            .collect();
+        assert_eq!(task_labels, vec!["Consulting", "Support"]);
+        assert_eq!(kafka.customer.as_ref().unwrap().name, "Company A");
+        // Also carries a tag as another kind of record with the same ID
+        assert_eq!(kafka.tags.len(), 1);
+        assert_eq!(kafka.tags[0].label, "de");
+        assert!(catalog.get(99).is_none());
+    }
+
+    #[test]
+    fn test_lookups() {
+        let catalog = catalog();
+
+        assert_eq!(
+            ids(catalog.by_name(" kafka kerberos integration")),
+            vec![10]
+        );
+        assert_eq!(ids(catalog.by_code("001106")), vec![11]);
+        assert_eq!(ids(catalog.by_client(1)), vec![10, 11]);
+        assert_eq!(ids(catalog.by_tag("DE")), vec![10, 11]);
+        assert_eq!(ids(catalog.by_tag("ch")), vec![12]);
+        assert!(catalog.by_tag("fr").is_empty());
+    }
+}
@@ -1,46 +1,33 @@
-use crate::coffeecup::structs::{Customer, Project, Tag, Task, TaskAssignment, TimeEntryWrapper, TrackingType, ValidationStatus};
-use chrono::Duration;
-use oauth2::basic::{
-    BasicClient, BasicErrorResponse, BasicRevocationErrorResponse, BasicTokenIntrospectionResponse,
-    BasicTokenResponse, BasicTokenType,
+use crate::coffeecup::auth::{Credentials, TokenManager};
+use crate::coffeecup::catalog::ProjectCatalog;
+use crate::coffeecup::config::CoffeeCupConfig;
+use crate::coffeecup::export::ExportRequest;
+use crate::coffeecup::structs::{
+    Absence, AbsenceFilter, AbsenceType, Customer, NewProject, Project, Tag, TagAssignment,
+    TagAssignmentFilter, TagFields, TagModel, Task, TaskAssignment, Team, TeamMembership,
+    TimeEntry, TimeEntryFilter, TimeEntryWrapper, User, UserEmployment,
 };
-use oauth2::{
-    AccessToken, AuthUrl, Client, ClientId, ClientSecret, EmptyExtraTokenFields, EndpointNotSet,
-    EndpointSet, ResourceOwnerPassword, ResourceOwnerUsername, StandardRevocableToken,
-    StandardTokenResponse, TokenResponse, TokenUrl,
-};
-use reqwest::header::{HeaderMap, HeaderValue, ACCEPT, AUTHORIZATION, CONTENT_TYPE};
-use reqwest::{Client as ReqwestClient, Url};
+use chrono::NaiveDate;
+use futures::StreamExt;
+use oauth2::basic::BasicErrorResponse;
+use oauth2::{HttpClientError, RequestTokenError};
+use reqwest::header::CONTENT_TYPE;
+use reqwest::{RequestBuilder, Response, StatusCode, Url};
 use serde::de::DeserializeOwned;
-use serde::{Deserialize, Serialize};
+use serde::{de, Deserialize, Deserializer, Serialize};
+use serde_json::json;
 use snafu::{ResultExt, Snafu};
-use std::str::FromStr;
-use std::sync::LazyLock;
-
-static TIMEENTRY_URL: LazyLock<Url> =
-    LazyLock::new(|| Url::from_str("https://api.coffeecupapp.com/v1/timeentries").unwrap());
-static TAGS_URL: LazyLock<Url> =
-    LazyLock::new(|| Url::from_str("https://api.coffeecup.app/v1/tags").unwrap());
-static TAGASSIGNMENTS_URL: LazyLock<Url> =
-    LazyLock::new(|| Url::from_str("https://api.coffeecup.app/v1/tagassignments").unwrap());
-static AUTH_URL: LazyLock<Url> =
-    LazyLock::new(|| Url::from_str("https://api.coffeecup.app/v1/tagassignments").unwrap());
-static TASKS_URL: LazyLock<Url> =
-    LazyLock::new(|| Url::from_str("https://api.coffeecup.app/v1/tasks").unwrap());
-static TASKASSIGNMENTS_URL: LazyLock<Url> =
-    LazyLock::new(|| Url::from_str("https://api.coffeecup.app/v1/taskassignments").unwrap());
-static PROJECTS_URL: LazyLock<Url> =
-    LazyLock::new(|| Url::from_str("https://api.coffeecup.app/v1/projects").unwrap());
-static CUSTOMER_URL: LazyLock<Url> =
-    LazyLock::new(|| Url::from_str("https://api.coffeecup.app/v1/clients").unwrap());
-static TOKEN_URL: LazyLock<Url> = LazyLock::new(|| {
-    Url::from_str(
-        "https://stackable.coffeecup.app/oauth2/token?companyurl=https://stackable.coffeecup.app",
-    )
-    .unwrap()
-});
+use std::collections::HashSet;
+use std::fmt::Debug;
+use std::path::PathBuf;
+
+const LIST_PAGE_SIZE: usize = 500;
+
+/// Number of entries [`CoffeeCup::create_timeentries`] creates at the same time.
+const CREATE_CONCURRENCY: usize = 8;
 
 #[derive(Snafu, Debug)]
+#[snafu(visibility(pub(crate)))]
 pub enum Error {
     #[snafu(display("Invalid header value specified [{value}]:  {source}"))]
     Header {
@@ -69,451 +56,771 @@ pub enum Error {
         url_type: String,
         source: oauth2::url::ParseError,
     },
+    #[snafu(display("Failed to obtain access token from [{url}]: {source}"))]
+    RequestToken {
+        source: RequestTokenError<HttpClientError<reqwest::Error>, BasicErrorResponse>,
+        url: String,
+    },
+    #[snafu(display("Failed to read token cache [{path}]: {source}"))]
+    ReadTokenCache {
+        source: std::io::Error,
+        path: String,
+    },
+    #[snafu(display("Failed to write token cache [{path}]: {source}"))]
+    WriteTokenCache {
+        source: std::io::Error,
+        path: String,
+    },
+    #[snafu(display(
+        "CoffeeCup rejected the access token for [{url}] even after a refresh: {body}"
+    ))]
+    Unauthorized { url: String, body: String },
+    #[snafu(display("Failed to {msg}, [{url}] responded with {status}: {body}"))]
+    Status {
+        msg: String,
+        url: String,
+        status: StatusCode,
+        body: String,
+    },
+    #[snafu(display("Failed to parse response from [{url}]: {source} - response was: {body}"))]
+    DecodeJson {
+        source: serde_json::Error,
+        url: String,
+        body: String,
+    },
+    #[snafu(display("CoffeeCup has no custom field [{number}], only 1 to 10"))]
+    InvalidCustomField { number: u8 },
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-struct TagAssigment {
-    id: usize,
-    record: usize,
-    model: String,
-    tag: usize,
+struct TagBody<T> {
+    tag: T,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-struct TimeEntry {
-    createdAt: String,
-    updatedAt: String,
-    id: usize,
-    trackingType: TrackingType,
-    startTime: Option<String>,
-    endTime: Option<String>,
-    day: String,
-    sorting: usize,
-    duration: usize,
-    durationRounded: usize,
-    durationRoundedOverride: Option<usize>,
-    estimate: Option<usize>,
-    running: bool,
-    comment: String,
-    hourlyRate: f64,
-    billedAt: Option<String>,
-    billable: bool,
-    validationStatus: ValidationStatus,
-    wasRejected: bool,
-    firstSubmissionTime: Option<String>,
-    approvedOn: Option<String>,
-    externalId: Option<String>,
-    team: usize,
-    task: usize,
-    project: usize,
-    user: usize,
-    invoice: usize,
-    approvedBy: Option<usize>,
+#[serde(rename_all = "camelCase")]
+struct TagAssignmentBody<T> {
+    tag_assignment: T,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-struct ListTagAssignmentResponse {
-    meta: ListResponseMeta,
-    #[serde(rename = "tagAssignments")]
-    tag_assignments: Vec<TagAssigment>,
+struct ProjectBody<T> {
+    project: T,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-struct ListTaskAssignmentResponse {
-    meta: ListResponseMeta,
-    #[serde(rename = "taskAssignments")]
-    taskAssignments: Vec<TaskAssignment>,
+struct TaskBody<T> {
+    task: T,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-struct ListTimeEntryResponse {
-    meta: ListResponseMeta,
-    #[serde(rename = "timeEntries")]
-    time_entries: Vec<TimeEntry>,
+#[serde(rename_all = "camelCase")]
+struct TaskAssignmentBody<T> {
+    task_assignment: T,
+}
+
+/// A task assignment that has not been created yet.
+#[derive(Debug, Serialize)]
+struct NewTaskAssignment {
+    project: usize,
+    task: usize,
 }
 
+/// A tag assignment that has not been created yet.
+#[derive(Debug, Serialize)]
+struct NewTagAssignment {
+    tag: usize,
+    model: TagModel,
+    record: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct UserResponse {
+    user: User,
+}
+
+/// Paging information CoffeeCup returns alongside every page of a list endpoint.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ListResponseMeta {
     skip: isize,
     limit: isize,
     total: isize,
+    #[serde(default)]
     sort: Vec<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ListTagResponse {
-    pub tags: Vec<Tag>,
-    pub meta: ListResponseMeta,
+impl ListResponseMeta {
+    /// Returns true if no further page needs to be requested after `received` items were
+    /// returned on this page and `fetched` items have been collected in total.
+    fn is_last_page(&self, received: usize, fetched: usize) -> bool {
+        received == 0 || fetched as isize >= self.total
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ListTaskResponse {
-    pub tasks: Vec<Task>,
+/// One page of a CoffeeCup list endpoint.
+///
+/// CoffeeCup returns the items of a page in a field named after the model (`projects`,
+/// `tagAssignments`, ...), which is looked up via [`ProvidesPayloadFieldName`].
+#[derive(Debug)]
+pub struct ListResponse<T> {
+    pub payload: Vec<T>,
     pub meta: ListResponseMeta,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ListResponse<T>
+impl<'de, T> Deserialize<'de> for ListResponse<T>
 where
-    T: ProvidesPayloadFieldName,
+    T: ProvidesPayloadFieldName + DeserializeOwned,
 {
-    #[serde(rename = "T::payload_field_name()")]
-    pub payload: Vec<T>,
-    pub meta: ListResponseMeta,
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let mut fields = serde_json::Map::deserialize(deserializer)?;
+        let meta = fields
+            .remove("meta")
+            .ok_or_else(|| de::Error::missing_field("meta"))?;
+        let payload = fields
+            .remove(T::payload_field_name())
+            .ok_or_else(|| de::Error::missing_field(T::payload_field_name()))?;
+
+        Ok(Self {
+            payload: serde_json::from_value(payload).map_err(de::Error::custom)?,
+            meta: serde_json::from_value(meta).map_err(de::Error::custom)?,
+        })
+    }
 }
 
+/// Implemented by every model that can be retrieved from a CoffeeCup list endpoint.
 pub trait ProvidesPayloadFieldName {
     fn payload_field_name() -> &'static str;
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ListProjectResponse {
-    pub projects: Vec<Project>,
-    pub meta: ListResponseMeta,
+impl ProvidesPayloadFieldName for Tag {
+    fn payload_field_name() -> &'static str {
+        "tags"
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ListCustomerResponse {
-    #[serde(rename="clients")]
-    pub customers: Vec<Customer>,
-    pub meta: ListResponseMeta,
+impl ProvidesPayloadFieldName for TagAssignment {
+    fn payload_field_name() -> &'static str {
+        "tagAssignments"
+    }
 }
 
-pub struct CoffeeCup {
-    client: reqwest::Client,
-    oauth_client: Client<
-        BasicErrorResponse,
-        BasicTokenResponse,
-        BasicTokenIntrospectionResponse,
-        StandardRevocableToken,
-        BasicRevocationErrorResponse,
-        EndpointNotSet,
-        EndpointNotSet,
-        EndpointNotSet,
-        EndpointNotSet,
-        EndpointSet,
-    >,
-    token: StandardTokenResponse<EmptyExtraTokenFields, BasicTokenType>,
+impl ProvidesPayloadFieldName for Task {
+    fn payload_field_name() -> &'static str {
+        "tasks"
+    }
 }
 
-impl CoffeeCup {
-    /*pub fn new(username: &str, password: &str) -> Result<Self, Error> {
-        let client = BasicClient::new(ClientId::new("ttt_importer".to_string()))
-            .set_client_secret(ClientSecret::new("ttt_importer".to_string()))
-            .set_token_uri(TokenUrl::new("https://stackable.coffeecup.app/oauth2/token?companyurl=https://stackable.coffeecup.app".to_string()).unwrap());
+impl ProvidesPayloadFieldName for TaskAssignment {
+    fn payload_field_name() -> &'static str {
+        "taskAssignments"
+    }
+}
 
-        let http_client = reqwest::blocking::ClientBuilder::new()
-            // Following redirects opens the client up to SSRF vulnerabilities.
-            .redirect(reqwest::redirect::Policy::none())
-            .build()
-            .expect("Client should build");
+impl ProvidesPayloadFieldName for Project {
+    fn payload_field_name() -> &'static str {
+        "projects"
+    }
+}
 
-        let token_result = client
-            .exchange_client_credentials()
-            .add_scope(Scope::new("".to_string()))
-            .request(&http_client).unwrap();
+impl ProvidesPayloadFieldName for Customer {
+    fn payload_field_name() -> &'static str {
+        "clients"
+    }
+}
 
+impl ProvidesPayloadFieldName for TimeEntry {
+    fn payload_field_name() -> &'static str {
+        "timeEntries"
+    }
+}
 
-        Ok(Self {
+impl ProvidesPayloadFieldName for User {
+    fn payload_field_name() -> &'static str {
+        "users"
+    }
+}
 
-        })
+impl ProvidesPayloadFieldName for Team {
+    fn payload_field_name() -> &'static str {
+        "teams"
+    }
+}
+
+impl ProvidesPayloadFieldName for TeamMembership {
+    fn payload_field_name() -> &'static str {
+        "teamMemberships"
+    }
+}
+
+impl ProvidesPayloadFieldName for AbsenceType {
+    fn payload_field_name() -> &'static str {
+        "absenceTypes"
     }
+}
 
+impl ProvidesPayloadFieldName for Absence {
+    fn payload_field_name() -> &'static str {
+        "absences"
+    }
+}
 
+impl ProvidesPayloadFieldName for UserEmployment {
+    fn payload_field_name() -> &'static str {
+        "userEmployments"
+    }
+}
 
-     */
+pub struct CoffeeCup {
+    config: CoffeeCupConfig,
+    client: reqwest::Client,
+    tokens: TokenManager,
+}
+
+impl CoffeeCup {
     pub async fn new_with_password(username: &str, password: &str) -> Result<Self, Error> {
-        let client = BasicClient::new(ClientId::new("client_id".to_string()))
-            .set_client_secret(ClientSecret::new("client_secret".to_string()))
-            .set_token_uri(TokenUrl::new(TOKEN_URL.to_string()).unwrap());
+        Self::new_with_config(
+            CoffeeCupConfig::default(),
+            Credentials::password(username, password),
+            None,
+        )
+        .await
+    }
 
+    /// Creates a client talking to the endpoints defined in `config` instead of the public
+    /// CoffeeCup API.
+    ///
+    /// If `token_cache` is set, tokens are persisted to that file and a still valid token from
+    /// an earlier run is reused instead of logging in again.
+    pub async fn new_with_config(
+        config: CoffeeCupConfig,
+        credentials: Credentials,
+        token_cache: Option<PathBuf>,
+    ) -> Result<Self, Error> {
         let http_client = reqwest::ClientBuilder::new()
             // Following redirects opens the client up to SSRF vulnerabilities.
             .redirect(reqwest::redirect::Policy::none())
             .build()
-            .expect("Client should build");
+            .context(ReqwestSnafu {
+                msg: "build client",
+            })?;
+
+        let mut tokens = TokenManager::new(&config, credentials, http_client.clone())?;
+        if let Some(token_cache) = token_cache {
+            tokens = tokens.with_cache_file(token_cache);
+        }
+        // Log in right away so that wrong credentials are reported here and not on first use
+        tokens.access_token().await?;
 
-        let token_result = client
-            .exchange_password(
-                &ResourceOwnerUsername::new(username.to_string()),
-                &ResourceOwnerPassword::new(password.to_string()),
-            )
-            .request_async(&http_client)
-            .await
-            .unwrap();
-        println!("{:?}", token_result);
         Ok(Self {
+            config,
             client: http_client,
-            oauth_client: client,
-            token: token_result,
+            tokens,
         })
     }
 
-    pub async fn get_token(&mut self) -> Result<String, Error> {
-        if let Some(expiry) = self.token.expires_in() {
-            if expiry < std::time::Duration::from_secs(60 * 60 * 24) {
-                println!("Token expired, refreshing..");
-                // Ticket is about to expire
-                let test = self
-                    .oauth_client
-                    .exchange_refresh_token(self.token.refresh_token().unwrap())
-                    .request_async(&self.client)
-                    .await
-                    .unwrap();
-                self.token = test;
-                println!("refresh done!")
+    pub async fn get_token(&self) -> Result<String, Error> {
+        self.tokens.access_token().await
+    }
+
+    /// Sends `request` with a bearer token and fails unless the server reported success.
+    ///
+    /// If the server rejects the token, a new one is obtained and the request is sent once more.
+    async fn send(&self, request: RequestBuilder, msg: &str, url: &Url) -> Result<Response, Error> {
+        let retry = request.try_clone();
+        let token = self.get_token().await?;
+        let mut response =
+            request
+                .bearer_auth(&token)
+                .send()
+                .await
+                .context(ReqwestWithUrlSnafu {
+                    msg,
+                    url: url.as_ref(),
+                })?;
+
+        if let Some(retry) = retry.filter(|_| response.status() == StatusCode::UNAUTHORIZED) {
+            let token = self.tokens.refresh_rejected(&token).await?;
+            response = retry
+                .bearer_auth(&token)
+                .send()
+                .await
+                .context(ReqwestWithUrlSnafu {
+                    msg,
+                    url: url.as_ref(),
+                })?;
+        }
+
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+        // The body usually tells what was wrong with the request, so include it in the error
+        let body = response.text().await.unwrap_or_default();
+        if status == StatusCode::UNAUTHORIZED {
+            UnauthorizedSnafu {
+                url: url.as_ref(),
+                body,
             }
+            .fail()
         } else {
-            // not sure ....
+            StatusSnafu {
+                msg,
+                url: url.as_ref(),
+                status,
+                body,
+            }
+            .fail()
         }
-        Ok(self.token.access_token().clone().into_secret())
-    }
-
-    pub async fn new(token: &str) -> Result<Self, Error> {
-        Self::new_with_password("", "").await
-        /*
-        // Build default headers to be included with every request later on
-        let mut default_headers = HeaderMap::new();
-        default_headers.insert(
-            AUTHORIZATION,
-            HeaderValue::from_str(&format!("Bearer {}", token))
-                .context(HeaderSnafu { value: token })?,
-        );
-        // This really shouldn't fail, but since we have the error variant .. why not?
-        default_headers.insert(
-            ACCEPT,
-            HeaderValue::from_str("application/json").context(HeaderSnafu {
-                value: "application/json",
-            })?,
-        );
+    }
 
-        Ok(Self {
-            client: ReqwestClient::builder()
-                .default_headers(default_headers)
-                .build()
-                .context(ReqwestSnafu {
-                    msg: "build client",
-                })?,
-            oauth_client: BasicClient::new(ClientId::new("client_id".to_string()))
-            .set_client_secret(ClientSecret::new("client_secret".to_string()))
-            .set_token_uri(TokenUrl::new(TOKEN_URL.to_string()).unwrap());,
-            token: (),
+    /// Like [`Self::send`], but parses the response as JSON.
+    async fn send_json<T>(&self, request: RequestBuilder, msg: &str, url: &Url) -> Result<T, Error>
+    where
+        T: DeserializeOwned,
+    {
+        let body =
+            self.send(request, msg, url)
+                .await?
+                .text()
+                .await
+                .context(ReqwestWithUrlSnafu {
+                    msg: format!("read response to {msg}"),
+                    url: url.as_ref(),
+                })?;
+
+        serde_json::from_str(&body).context(DecodeJsonSnafu {
+            url: url.as_ref(),
+            body,
         })
+    }
+
+    /// Retrieves all projects with their tasks, customers and tags.
+    pub async fn get_my_projects(&self) -> Result<ProjectCatalog, Error> {
+        let project_tags = TagAssignmentFilter {
+            model: Some(TagModel::Project),
+            ..TagAssignmentFilter::default()
+        };
+        let (projects, tasks, task_assignments, customers, tags, tag_assignments) = tokio::try_join!(
+            self.list_projects(),
+            self.list_tasks(),
+            self.list_task_assignments(),
+            self.list_customer(),
+            self.list_tags(),
+            self.get_tag_assignments(&project_tags),
+        )?;
 
-         */
+        Ok(ProjectCatalog::new(
+            projects,
+            &tasks,
+            &task_assignments,
+            &customers,
+            &tags,
+            &tag_assignments,
+        ))
     }
 
-    pub async fn get_my_projects(&mut self) -> Result<Vec<Project>, Error> {
-        let mut projects = self.list_projects().await.unwrap();
-        let tasks = self.list_tasks().await.unwrap();
-        println!("Tasks: {:?}", tasks);
-        let task_assignments = self.list_task_assignments().await.unwrap();
+    /// Retrieves every item of a list endpoint.
+    ///
+    /// CoffeeCup returns at most [`LIST_PAGE_SIZE`] items per request, so this keeps requesting
+    /// pages until the `total` reported by the server has been fetched. `endpoint` is resolved
+    /// against the configured base url, `query` is sent along with every page request and
+    /// `what` is only used to describe failed requests.
+    async fn list_all<T>(
+        &self,
+        endpoint: &str,
+        query: &[(&str, String)],
+        what: &str,
+    ) -> Result<Vec<T>, Error>
+    where
+        T: ProvidesPayloadFieldName + DeserializeOwned,
+    {
+        let url = self.config.endpoint(endpoint)?;
+        let mut items: Vec<T> = Vec::new();
 
+        loop {
+            let request = self
+                .client
+                .get(url.as_ref())
+                .header(CONTENT_TYPE, "application/json")
+                .query(query)
+                .query(&[
+                    ("limit", LIST_PAGE_SIZE.to_string()),
+                    ("skip", items.len().to_string()),
+                ]);
 
-        let mut result: Vec<Project> = Vec::new();
-        for mut project in projects {
-            let task_ids:Vec<usize> = task_assignments
-                .iter()
-                .filter(|task_assignment| task_assignment.project.eq(&project.id))
-                .map(|task_assignment: &TaskAssignment| task_assignment.task)
-                .collect();
-            println!("Task ids for project {:?}: {:?}", project.name, task_ids);
-            let tasks: Vec<Task> = tasks.iter().filter(|task| task_ids.contains(&task.id)).map(|task| task.clone()).collect();
-            println!("Tasks for project {:?}: {:?}", project.name, tasks);
-            let mut new_project = project.clone();
-            new_project.tasks = Some(tasks);
-            result.push(new_project);
+            let page: ListResponse<T> = self
+                .send_json(request, &format!("send list {what} request"), &url)
+                .await?;
 
+            let received = page.payload.len();
+            items.extend(page.payload);
+            if page.meta.is_last_page(received, items.len()) {
+                break;
+            }
         }
-        println!("Projects: {:?}", result);
-
-        Ok(result)
+        Ok(items)
     }
 
-    pub async fn list_projects(&mut self) -> Result<Vec<Project>, Error> {
-        let request = self
-            .client
-            .get(PROJECTS_URL.as_ref())
-            .header(CONTENT_TYPE, "application/json")
-            .bearer_auth(&mut self.get_token().await?)
-            .build()
-            .context(ReqwestSnafu {
-                msg: "build list projects request",
-            })?;
+    pub async fn list_projects(&self) -> Result<Vec<Project>, Error> {
+        self.list_all("projects", &[], "projects").await
+    }
 
-        let response = self
-            .client
-            .execute(request)
-            .await
-            .context(ReqwestWithUrlSnafu {
-                msg: "send list projects request",
-                url: PROJECTS_URL.as_ref(),
-            })?;
-        println!("{:?}", response);
-        Ok(response
-            .json::<ListProjectResponse>()
-            .await
-            .unwrap()
-            .projects)
+    pub async fn list_customer(&self) -> Result<Vec<Customer>, Error> {
+        self.list_all("clients", &[], "customers").await
     }
 
-    pub async fn list_customer(&mut self) -> Result<Vec<Customer>, Error> {
-        let request = self
-            .client
-            .get(CUSTOMER_URL.as_ref())
-            .header(CONTENT_TYPE, "application/json")
-            .bearer_auth(&mut self.get_token().await?)
-            .build()
-            .context(ReqwestSnafu {
-                msg: "build list customer request",
-            })?;
+    pub async fn list_tasks(&self) -> Result<Vec<Task>, Error> {
+        self.list_all("tasks", &[], "tasks").await
+    }
 
-        let response = self
-            .client
-            .execute(request)
+    pub async fn list_task_assignments(&self) -> Result<Vec<TaskAssignment>, Error> {
+        self.list_all("taskassignments", &[], "task assignments")
             .await
-            .context(ReqwestWithUrlSnafu {
-                msg: "send list customer request",
-                url: CUSTOMER_URL.as_ref(),
-            })?;
-        println!("{:?}", response);
-        Ok(response
-            .json::<ListCustomerResponse>()
-            .await
-            .unwrap()
-            .customers)
     }
-    async fn list_tasks(&mut self) -> Result<Vec<Task>, Error> {
-        let request = self
-            .client
-            .get(TASKS_URL.as_ref())
-            .header(CONTENT_TYPE, "application/json")
-            .bearer_auth(&mut self.get_token().await?)
-            .build()
-            .context(ReqwestSnafu {
-                msg: "build list tasks request",
-            })?;
 
-        let response = self
-            .client
-            .execute(request)
-            .await
-            .context(ReqwestWithUrlSnafu {
-                msg: "send list tasks request",
-                url: TIMEENTRY_URL.as_ref(),
-            })?
-            .json::<ListTaskResponse>()
-            .await
-            .unwrap();
-        Ok(response.tasks)
+    pub async fn list_users(&self) -> Result<Vec<User>, Error> {
+        self.list_all("users", &[], "users").await
     }
 
-    async fn list_task_assignments(&mut self) -> Result<Vec<TaskAssignment>, Error> {
-        let request = self
-            .client
-            .get(TASKASSIGNMENTS_URL.as_ref())
-            .header(CONTENT_TYPE, "application/json")
-            .bearer_auth(&mut self.get_token().await?)
-            .build()
-            .context(ReqwestSnafu {
-                msg: "build list task assignments request",
-            })?;
+    pub async fn list_teams(&self) -> Result<Vec<Team>, Error> {
+        self.list_all("teams", &[], "teams").await
+    }
 
-        let response = self
-            .client
-            .execute(request)
-            .await
-            .context(ReqwestWithUrlSnafu {
-                msg: "send list task assignments request",
-                url: TASKASSIGNMENTS_URL.as_ref(),
-            })?
-            .json::<ListTaskAssignmentResponse>()
+    /// Creates a project and returns it as stored by CoffeeCup, including its ID.
+    pub async fn create_project(&self, project: &NewProject) -> Result<Project, Error> {
+        let url = self.config.endpoint("projects")?;
+        let request = self.client.post(url.as_ref());
+        let response: ProjectBody<Project> = self
+            .send_body(
+                request,
+                &ProjectBody { project },
+                "send create project request",
+                &url,
+            )
+            .await?;
+        Ok(response.project)
+    }
+
+    /// Archives the project with the ID `id`, which hides it from time tracking.
+    pub async fn archive_project(&self, id: usize) -> Result<Project, Error> {
+        self.change_project(id, json!({ "status": 0 }), "send archive project request")
             .await
-            .unwrap();
-        Ok(response.taskAssignments)
     }
 
-    async fn get_tag_assigments(
+    /// Marks the project with the ID `id` as completed on `day`.
+    pub async fn complete_project(&self, id: usize, day: NaiveDate) -> Result<Project, Error> {
+        self.change_project(
+            id,
+            json!({ "completedAt": day.to_string() }),
+            "send complete project request",
+        )
+        .await
+    }
+
+    /// Sends `changes` to the project with the ID `id`, fields that are not part of `changes`
+    /// are kept.
+    async fn change_project(
         &self,
-        tag_id: usize,
-        model: &str,
-    ) -> Result<Vec<TagAssigment>, Error> {
+        id: usize,
+        changes: serde_json::Value,
+        msg: &str,
+    ) -> Result<Project, Error> {
+        let url = self.config.endpoint(&format!("projects/{id}"))?;
+        let request = self.client.put(url.as_ref());
+
+        let response: ProjectBody<Project> = self
+            .send_body(request, &ProjectBody { project: changes }, msg, &url)
+            .await?;
+        Ok(response.project)
+    }
+
+    /// Creates a task with the label `label`, which can then be assigned to projects.
+    pub async fn create_task(&self, label: &str) -> Result<Task, Error> {
+        let url = self.config.endpoint("tasks")?;
+        let request = self.client.post(url.as_ref());
+        let body = TaskBody {
+            task: json!({ "label": label }),
+        };
+
+        let response: TaskBody<Task> = self
+            .send_body(request, &body, "send create task request", &url)
+            .await?;
+        Ok(response.task)
+    }
+
+    /// Allows tracking time on the task with the ID `task` in the project with the ID
+    /// `project`.
+    pub async fn assign_task(&self, project: usize, task: usize) -> Result<TaskAssignment, Error> {
+        let url = self.config.endpoint("taskassignments")?;
+        let request = self.client.post(url.as_ref());
+        let body = TaskAssignmentBody {
+            task_assignment: NewTaskAssignment { project, task },
+        };
+
+        let response: TaskAssignmentBody<TaskAssignment> = self
+            .send_body(request, &body, "send assign task request", &url)
+            .await?;
+        Ok(response.task_assignment)
+    }
+
+    /// Retrieves the user with the ID `id`.
+    pub async fn get_user(&self, id: usize) -> Result<User, Error> {
+        let url = self.config.endpoint(&format!("users/{id}"))?;
         let request = self
             .client
-            .get(TAGASSIGNMENTS_URL.as_ref())
-            .header(CONTENT_TYPE, "application/json")
-            .build()
-            .context(ReqwestSnafu {
-                msg: "build list tag assignments request",
-            })?;
+            .get(url.as_ref())
+            .header(CONTENT_TYPE, "application/json");
 
-        let response = self
-            .client
-            .execute(request)
-            .await
-            .context(ReqwestWithUrlSnafu {
-                msg: "send create request",
-                url: TIMEENTRY_URL.as_ref(),
-            })?
-            .json::<ListTagAssignmentResponse>()
+        let response: UserResponse = self
+            .send_json(request, "send get user request", &url)
+            .await?;
+        Ok(response.user)
+    }
+
+    /// Returns the user with the email address `email`, ignoring case, if there is one.
+    pub async fn find_user_by_email(&self, email: &str) -> Result<Option<User>, Error> {
+        let email = email.trim();
+        Ok(self
+            .list_users()
+            .await?
+            .into_iter()
+            .find(|user| user.email.eq_ignore_ascii_case(email)))
+    }
+
+    pub async fn list_team_memberships(&self) -> Result<Vec<TeamMembership>, Error> {
+        self.list_all("teammemberships", &[], "team memberships")
             .await
-            .unwrap();
+    }
+
+    /// Retrieves the users who are members of the team with the ID `team`.
+    pub async fn get_team_members(&self, team: usize) -> Result<Vec<User>, Error> {
+        let filter = [("where", json!({ "team": team }).to_string())];
+        let (memberships, users) = tokio::try_join!(
+            self.list_all::<TeamMembership>("teammemberships", &filter, "team memberships"),
+            self.list_users(),
+        )?;
 
-        Ok(response
-            .tag_assignments
+        let members: HashSet<usize> = memberships
+            .iter()
+            .map(|membership| membership.user)
+            .collect();
+        Ok(users
             .into_iter()
-            .filter(|assignment| assignment.model.eq(model) && assignment.tag.eq(&tag_id))
+            .filter(|user| members.contains(&user.id))
             .collect())
     }
 
-    pub async fn get_project_ids_by_tag(&mut self, tag: &str) -> Result<Vec<usize>, Error> {
+    /// Retrieves all tag assignments matching `filter`.
+    pub async fn get_tag_assignments(
+        &self,
+        filter: &TagAssignmentFilter,
+    ) -> Result<Vec<TagAssignment>, Error> {
+        self.list_all(
+            "tagassignments",
+            &[("where", filter.where_clause().to_string())],
+            "tag assignments",
+        )
+        .await
+    }
+
+    pub async fn get_project_ids_by_tag(&self, tag: &str) -> Result<Vec<usize>, Error> {
         let tag_id = self.find_tag_by_name(tag).await?;
 
-        let projects = self.get_tag_assigments(tag_id, "project").await.unwrap();
-        println!("Found projects: {:?}", projects);
-        Ok(projects
+        let assignments = self
+            .get_tag_assignments(&TagAssignmentFilter::tag(tag_id, TagModel::Project))
+            .await?;
+        Ok(assignments
             .iter()
             .map(|assignment| assignment.record)
             .collect())
     }
 
-    pub async fn get_timeentries(
-        &self,
-        team: usize,
-        projects: Option<Vec<usize>>,
-    ) -> Result<Vec<TimeEntry>, Error> {
-        Ok(Vec::new())
+    /// Retrieves all time entries matching `filter`.
+    pub async fn get_timeentries(&self, filter: &TimeEntryFilter) -> Result<Vec<TimeEntry>, Error> {
+        self.list_all(
+            "timeentries",
+            &[("where", filter.where_clause().to_string())],
+            "time entries",
+        )
+        .await
     }
 
-    async fn find_tag_by_name(&mut self, tag_name: &str) -> Result<usize, Error> {
-        let request = self
-            .client
-            .get(TAGS_URL.as_ref())
-            .header(CONTENT_TYPE, "application/json")
-            .bearer_auth(&self.get_token().await.unwrap())
-            .build()
-            .context(ReqwestSnafu {
-                msg: "build create request",
-            })?;
+    pub async fn list_absence_types(&self) -> Result<Vec<AbsenceType>, Error> {
+        self.list_all("absencetypes", &[], "absence types").await
+    }
 
-        let response = self
-            .client
-            .execute(request)
+    /// Retrieves all absences matching `filter`.
+    pub async fn get_absences(&self, filter: &AbsenceFilter) -> Result<Vec<Absence>, Error> {
+        self.list_all(
+            "absences",
+            &[("where", filter.where_clause().to_string())],
+            "absences",
+        )
+        .await
+    }
+
+    /// Retrieves the working times of all users, see [`UserEmployment::applicable`].
+    pub async fn list_user_employments(&self) -> Result<Vec<UserEmployment>, Error> {
+        self.list_all("useremployments", &[], "user employments")
             .await
-            .context(ReqwestWithUrlSnafu {
-                msg: "send create request",
-                url: TIMEENTRY_URL.as_ref(),
-            })?
-            .json::<ListTagResponse>()
+    }
+
+    /// Retrieves the working times of the user with the ID `user`.
+    pub async fn get_user_employments(&self, user: usize) -> Result<Vec<UserEmployment>, Error> {
+        self.list_all(
+            "useremployments",
+            &[("where", json!({ "user": user }).to_string())],
+            "user employments",
+        )
+        .await
+    }
+
+    /// Downloads the export described by `request`, for example a PDF hour overview.
+    ///
+    /// Exports are rendered by the tenant, not by the API host. Like the download links of the
+    /// web UI, the request carries the token as `access_token` parameter in addition to the
+    /// header, as the tenant authenticates downloads by the parameter.
+    pub async fn export_timeentries(&self, request: &ExportRequest) -> Result<Vec<u8>, Error> {
+        let url = self.config.export_url("timeEntries")?;
+        let http_request = |token: &str| {
+            self.client
+                .get(url.as_ref())
+                .query(&request.query())
+                .query(&[("access_token", token)])
+        };
+
+        let token = self.get_token().await?;
+        let response = match self
+            .send(http_request(&token), "send export request", &url)
             .await
-            .unwrap();
+        {
+            // The retry in `send` only renews the header, so the parameter is renewed here
+            Err(Error::Unauthorized { .. }) => {
+                let token = self.tokens.refresh_rejected(&token).await?;
+                self.send(http_request(&token), "send export request", &url)
+                    .await?
+            }
+            response => response?,
+        };
+        let bytes = response.bytes().await.context(ReqwestWithUrlSnafu {
+            msg: "read export",
+            url: url.as_ref(),
+        })?;
+        Ok(bytes.to_vec())
+    }
 
-        // Find tag matching the name we are looking for
-        let mut matching_tags: Vec<&Tag> = response
-            .tags
+    pub async fn list_tags(&self) -> Result<Vec<Tag>, Error> {
+        self.list_all("tags", &[], "tags").await
+    }
+
+    /// Creates a tag and returns it as stored by CoffeeCup, including its ID.
+    pub async fn create_tag(&self, fields: &TagFields) -> Result<Tag, Error> {
+        let url = self.config.endpoint("tags")?;
+        let request = self.client.post(url.as_ref());
+
+        let response: TagBody<Tag> = self
+            .send_body(
+                request,
+                &TagBody { tag: fields },
+                "send create tag request",
+                &url,
+            )
+            .await?;
+        Ok(response.tag)
+    }
+
+    /// Changes the tag with the ID `id` to `fields` and returns it as stored by CoffeeCup.
+    pub async fn update_tag(&self, id: usize, fields: &TagFields) -> Result<Tag, Error> {
+        let url = self.config.endpoint(&format!("tags/{id}"))?;
+        let request = self.client.put(url.as_ref());
+
+        let response: TagBody<Tag> = self
+            .send_body(
+                request,
+                &TagBody { tag: fields },
+                "send update tag request",
+                &url,
+            )
+            .await?;
+        Ok(response.tag)
+    }
+
+    /// Deletes the tag with the ID `id`.
+    pub async fn delete_tag(&self, id: usize) -> Result<(), Error> {
+        let url = self.config.endpoint(&format!("tags/{id}"))?;
+        let request = self.client.delete(url.as_ref());
+
+        self.send(request, "send delete tag request", &url).await?;
+
+        Ok(())
+    }
+
+    /// Assigns the tag with the ID `tag` to the record of kind `model` with the ID `record`.
+    pub async fn assign_tag(
+        &self,
+        tag: usize,
+        model: TagModel,
+        record: usize,
+    ) -> Result<TagAssignment, Error> {
+        let url = self.config.endpoint("tagassignments")?;
+        let request = self.client.post(url.as_ref());
+        let body = TagAssignmentBody {
+            tag_assignment: NewTagAssignment { tag, model, record },
+        };
+
+        let response: TagAssignmentBody<TagAssignment> = self
+            .send_body(request, &body, "send assign tag request", &url)
+            .await?;
+        Ok(response.tag_assignment)
+    }
+
+    /// Deletes the tag assignment with the ID `id`, which leaves the tag itself in place.
+    pub async fn delete_tag_assignment(&self, id: usize) -> Result<(), Error> {
+        let url = self.config.endpoint(&format!("tagassignments/{id}"))?;
+        let request = self.client.delete(url.as_ref());
+
+        self.send(request, "send delete tag assignment request", &url)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Retrieves the tags assigned to the record of kind `model` with the ID `record`.
+    pub async fn get_tags_for_record(
+        &self,
+        model: TagModel,
+        record: usize,
+    ) -> Result<Vec<Tag>, Error> {
+        let assignments = self
+            .get_tag_assignments(&TagAssignmentFilter::record(model, record))
+            .await?;
+        let mut tag_ids: Vec<usize> = assignments
             .iter()
-            .filter(|tag| tag.label.eq(tag_name))
+            .map(|assignment| assignment.tag)
             .collect();
+        if tag_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        tag_ids.sort_unstable();
+        tag_ids.dedup();
+
+        self.list_all(
+            "tags",
+            &[("where", json!({ "id": { "in": tag_ids } }).to_string())],
+            "tags",
+        )
+        .await
+    }
+
+    async fn find_tag_by_name(&self, tag_name: &str) -> Result<usize, Error> {
+        let tags = self.list_tags().await?;
+
+        // Find tag matching the name we are looking for
+        let mut matching_tags: Vec<&Tag> =
+            tags.iter().filter(|tag| tag.label.eq(tag_name)).collect();
         if let Some(found_tag) = matching_tags.pop() {
             if matching_tags.is_empty() {
                 // We took the only tag that was found - success!
@@ -533,72 +840,1119 @@ impl CoffeeCup {
         }
     }
 
-    pub async fn create_timeentry(&self, entry: &TimeEntryWrapper) -> Result<(), Error> {
+    /// Retrieves the time entry with the ID `id`.
+    pub async fn get_timeentry(&self, id: usize) -> Result<TimeEntry, Error> {
+        let url = self.config.endpoint(&format!("timeentries/{id}"))?;
         let request = self
             .client
-            .post(TIMEENTRY_URL.as_ref())
-            .header(CONTENT_TYPE, "application/json")
-            .body(
-                serde_json::to_string(&entry).context(SerializeRequestSnafu {
-                    msg: format!("{:?}", entry),
-                })?,
-            )
-            .build()
-            .context(ReqwestSnafu {
-                msg: "build create request",
-            })?;
+            .get(url.as_ref())
+            .header(CONTENT_TYPE, "application/json");
 
-        let response = self
-            .client
-            .execute(request)
+        let response: TimeEntryWrapper = self
+            .send_json(request, "send get time entry request", &url)
+            .await?;
+        Ok(response.timeEntry)
+    }
+
+    /// Creates `entry` and returns it as stored by CoffeeCup, including its ID.
+    pub async fn create_timeentry(&self, entry: &TimeEntry) -> Result<TimeEntry, Error> {
+        let url = self.config.endpoint("timeentries")?;
+        let request = self.client.post(url.as_ref());
+
+        self.write_timeentry(request, entry, "send create request", &url)
             .await
-            .context(ReqwestWithUrlSnafu {
-                msg: "send create request",
-                url: TIMEENTRY_URL.as_ref(),
-            })?;
+    }
+
+    /// Creates all `entries`, a few at a time.
+    ///
+    /// Failing to create an entry doesn't stop the others from being created, so the result for
+    /// every entry is returned in the order of `entries`.
+    pub async fn create_timeentries(&self, entries: &[TimeEntry]) -> Vec<Result<TimeEntry, Error>> {
+        futures::stream::iter(entries)
+            .map(|entry| self.create_timeentry(entry))
+            .buffered(CREATE_CONCURRENCY)
+            .collect()
+            .await
+    }
+
+    /// Replaces the time entry with the ID `id` by `entry` and returns it as stored by
+    /// CoffeeCup.
+    ///
+    /// The fields CoffeeCup maintains itself, such as the ID, timestamps, rounded duration and
+    /// hourly rate, are left out of the request.
+    pub async fn update_timeentry(&self, id: usize, entry: &TimeEntry) -> Result<TimeEntry, Error> {
+        let url = self.config.endpoint(&format!("timeentries/{id}"))?;
+        let request = self.client.put(url.as_ref());
+        let entry = TimeEntry {
+            id: None,
+            createdAt: None,
+            updatedAt: None,
+            durationRounded: None,
+            hourlyRate: None,
+            ..entry.clone()
+        };
+
+        self.write_timeentry(request, &entry, "send update request", &url)
+            .await
+    }
+
+    /// Deletes the time entry with the ID `id`.
+    pub async fn delete_timeentry(&self, id: usize) -> Result<(), Error> {
+        let url = self.config.endpoint(&format!("timeentries/{id}"))?;
+        let request = self.client.delete(url.as_ref());
+
+        self.send(request, "send delete request", &url).await?;
 
         Ok(())
     }
+
+    async fn write_timeentry(
+        &self,
+        request: RequestBuilder,
+        entry: &TimeEntry,
+        msg: &str,
+        url: &Url,
+    ) -> Result<TimeEntry, Error> {
+        let body = TimeEntryWrapper {
+            timeEntry: entry.clone(),
+        };
+
+        let response: TimeEntryWrapper = self.send_body(request, &body, msg, url).await?;
+        Ok(response.timeEntry)
+    }
+
+    /// Sends `body` as JSON with `request` and parses the response.
+    async fn send_body<B, T>(
+        &self,
+        request: RequestBuilder,
+        body: &B,
+        msg: &str,
+        url: &Url,
+    ) -> Result<T, Error>
+    where
+        B: Serialize + Debug,
+        T: DeserializeOwned,
+    {
+        let request = request.header(CONTENT_TYPE, "application/json").body(
+            serde_json::to_string(body).context(SerializeRequestSnafu {
+                msg: format!("{:?}", body),
+            })?,
+        );
+
+        self.send_json(request, msg, url).await
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::bdwh::Bdwh;
-    use crate::coffeecup::client::CoffeeCup;
-    use crate::coffeecup::structs::TimeEntryWrapper;
-    use baserow_client::client::Client;
+    use crate::coffeecup::auth::{write_cache, CachedToken, Credentials};
+    use crate::coffeecup::client::{CoffeeCup, Error, ListResponse, ListResponseMeta};
+    use crate::coffeecup::config::CoffeeCupConfig;
+    use crate::coffeecup::conversion::ConversionPolicy;
+    use crate::coffeecup::export::{ExportFormat, ExportRequest};
+    use crate::coffeecup::mock::{
+        bdwh_entry, client_for, fixture, json_response, mock_list, mock_projects, mock_server,
+        resolver, timeentry_response,
+    };
+    use crate::coffeecup::structs::{
+        AbsenceFilter, BillBy, Budget, Customer, NewProject, Project, TagAssignmentFilter,
+        TagFields, TagModel, TimeEntry, TimeEntryFilter, TimeEntryWrapper, TrackingType,
+        UserEmployment, ValidationStatus,
+    };
+    use chrono::{NaiveDate, TimeDelta, Utc};
+    use reqwest::StatusCode;
+    use serde_json::json;
+    use std::fs::File;
+    use std::io::BufReader;
+    use wiremock::matchers::{
+        body_json, body_partial_json, body_string_contains, header, method, path, query_param,
+    };
+    use wiremock::{Mock, MockServer, Request, ResponseTemplate};
+
+    #[test]
+    fn test_parse_timeentries_page() {
+        let file = File::open("testdata/coffeecup/list_timeentries.json")
+            .expect("Failed to read test data from file!");
+        let reader = BufReader::new(file);
+
+        let page: ListResponse<TimeEntry> =
+            serde_json::from_reader(reader).expect("Failed to parse time entries!");
+
+        assert_eq!(page.meta.total, 2);
+        assert_eq!(page.payload.len(), 2);
+        assert_eq!(
+            page.payload[0].day,
+            NaiveDate::from_ymd_opt(2025, 6, 2).unwrap()
+        );
+        assert_eq!(page.payload[1].validationStatus, ValidationStatus::APPROVED);
+    }
+
+    #[test]
+    fn test_parse_list_response_payload_field() {
+        let page: ListResponse<Customer> = serde_json::from_value(json!({
+            "clients": [{ "id": 1, "status": 1, "name": "Company A" }],
+            "meta": { "skip": 0, "limit": 500, "total": 1 }
+        }))
+        .expect("Failed to parse customers!");
+        assert_eq!(page.payload[0].name, "Company A");
+
+        // The payload is looked up by the model's field name, not by a generic one
+        let result = serde_json::from_value::<ListResponse<Project>>(json!({
+            "clients": [],
+            "meta": { "skip": 0, "limit": 500, "total": 0 }
+        }));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_list_paging() {
+        let meta = ListResponseMeta {
+            skip: 0,
+            limit: 500,
+            total: 1200,
+            sort: vec![],
+        };
+        assert!(!meta.is_last_page(500, 500));
+        assert!(!meta.is_last_page(500, 1000));
+        assert!(meta.is_last_page(200, 1200));
+        // Stop when the server runs dry even if it promised more
+        assert!(meta.is_last_page(0, 1000));
+    }
+
+    #[test]
+    fn test_timeentry_filter_where_clause() {
+        let filter = TimeEntryFilter {
+            team: Some(3),
+            projects: Some(vec![10, 11]),
+            user: None,
+            from: NaiveDate::from_ymd_opt(2025, 6, 1),
+            to: NaiveDate::from_ymd_opt(2025, 6, 30),
+            validation_status: Some(ValidationStatus::APPROVED),
+            external_ids: Some(vec!["bdwh-1".to_string()]),
+        };
+
+        assert_eq!(
+            filter.where_clause(),
+            json!({
+                "team": 3,
+                "project": { "in": [10, 11] },
+                "day": { ">=": "2025-06-01", "<=": "2025-06-30" },
+                "validationStatus": "APPROVED",
+                "externalId": { "in": ["bdwh-1"] }
+            })
+        );
+        assert_eq!(TimeEntryFilter::default().where_clause(), json!({}));
+    }
+
+    #[test]
+    fn test_absence_filter_where_clause() {
+        let filter = AbsenceFilter {
+            user: Some(42),
+            from: NaiveDate::from_ymd_opt(2025, 6, 1),
+            to: NaiveDate::from_ymd_opt(2025, 6, 30),
+        };
+
+        assert_eq!(
+            filter.where_clause(),
+            json!({
+                "user": 42,
+                "endDate": { ">=": "2025-06-01" },
+                "startDate": { "<=": "2025-06-30" }
+            })
+        );
+        assert_eq!(AbsenceFilter::default().where_clause(), json!({}));
+    }
 
     #[tokio::test]
-    async fn test_get_tag() {
-        let mut cc_client = CoffeeCup::new("519834cd47c02c47e16c14f1ca0522f643956d95")
+    async fn test_get_absences() {
+        let server = mock_server().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/absences"))
+            .and(query_param("where", r#"{"user":42}"#))
+            .respond_with(json_response("list_absences.json"))
+            .mount(&server)
+            .await;
+        mock_list(&server, "absencetypes", "list_absencetypes.json").await;
+        let cc_client = client_for(&server).await;
+
+        let absences = cc_client
+            .get_absences(&AbsenceFilter {
+                user: Some(42),
+                ..AbsenceFilter::default()
+            })
             .await
             .unwrap();
+        let types = cc_client.list_absence_types().await.unwrap();
 
-        assert_eq!(cc_client.find_tag_by_name("de").await.unwrap(), 5502);
+        assert_eq!(absences.len(), 2);
+        let vacation = &absences[0];
+        assert!(types[vacation.absenceType - 1].isVacation);
+        let day = |day| NaiveDate::from_ymd_opt(2025, 6, day).unwrap();
+        assert_eq!(vacation.share_of(day(9)), 0.0);
+        assert_eq!(vacation.share_of(day(10)), 1.0);
+        assert_eq!(vacation.share_of(day(13)), 0.5);
     }
 
     #[tokio::test]
-    async fn test_get_project_by_tag() {
-        let mut cc_client = CoffeeCup::new("519834cd47c02c47e16c14f1ca0522f643956d95")
+    async fn test_user_employments() {
+        let server = mock_server().await;
+        mock_list(&server, "useremployments", "list_useremployments.json").await;
+        let cc_client = client_for(&server).await;
+
+        let employments = cc_client.list_user_employments().await.unwrap();
+
+        // Fridays off since April 2025
+        let friday = |year, month, day| NaiveDate::from_ymd_opt(year, month, day).unwrap();
+        let before = UserEmployment::applicable(&employments, 42, friday(2025, 3, 28)).unwrap();
+        assert_eq!(before.working_time(friday(2025, 3, 28)), 28800);
+        let after = UserEmployment::applicable(&employments, 42, friday(2025, 6, 6)).unwrap();
+        assert_eq!(after.working_time(friday(2025, 6, 6)), 0);
+        assert!(UserEmployment::applicable(&employments, 42, friday(2023, 12, 29)).is_none());
+        assert!(UserEmployment::applicable(&employments, 43, friday(2025, 6, 6)).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_export_timeentries() {
+        let server = mock_server().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/export/timeEntries"))
+            .and(query_param("start_date", "2025-06-01T00:00:00.000Z"))
+            .and(query_param("columns[comment]", "true"))
+            .and(query_param("format", "csv"))
+            .and(query_param(
+                "access_token",
+                "3a8f1c0e2d4b6a7988e1f0c2d3b4a5968778695a",
+            ))
+            .and(header(
+                "authorization",
+                "Bearer 3a8f1c0e2d4b6a7988e1f0c2d3b4a5968778695a",
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(b"date;hours\n".to_vec()))
+            .mount(&server)
+            .await;
+        // Exports are served by the tenant, the API is found elsewhere
+        let config =
+            CoffeeCupConfig::new(&format!("{}/api/v1", server.uri()), &server.uri()).unwrap();
+        let cc_client = CoffeeCup::new_with_config(
+            config,
+            Credentials::password("user@example.com", "secret"),
+            None,
+        )
+        .await
+        .unwrap();
+
+        let export = cc_client
+            .export_timeentries(
+                &ExportRequest::month(2025, 6)
+                    .unwrap()
+                    .format(ExportFormat::Csv),
+            )
             .await
             .unwrap();
 
-        let tag_id = cc_client.find_tag_by_name("de").await.unwrap();
+        assert_eq!(export, b"date;hours\n");
+    }
+
+    #[tokio::test]
+    async fn test_export_retries_with_new_token() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/oauth2/token"))
+            .and(body_string_contains("grant_type=password"))
+            .respond_with(json_response("token.json"))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/oauth2/token"))
+            .and(body_string_contains("grant_type=refresh_token"))
+            .respond_with(json_response("token_refreshed.json"))
+            .expect(1)
+            .mount(&server)
+            .await;
+        for (token, status) in [
+            ("3a8f1c0e2d4b6a7988e1f0c2d3b4a5968778695a", 401),
+            ("9d0e1f2a3b4c5d6e7f8091a2b3c4d5e6f7a8b9c0", 200),
+        ] {
+            Mock::given(method("GET"))
+                .and(path("/v1/export/timeEntries"))
+                .and(query_param("access_token", token))
+                .respond_with(ResponseTemplate::new(status).set_body_bytes(b"%PDF".to_vec()))
+                .mount(&server)
+                .await;
+        }
+        let cc_client = client_for(&server).await;
+
+        let export = cc_client
+            .export_timeentries(&ExportRequest::month(2025, 6).unwrap())
+            .await
+            .unwrap();
 
-        //let test = cc_client.get_project_by_tag("de").await.unwrap();
+        assert_eq!(export, b"%PDF");
     }
 
     #[tokio::test]
-    async fn test_async() {
-        let mut cc_client = CoffeeCup::new_with_password(
-            "soenke.liebau@stackable.tech",
-            "Rift-Safari7-Untidy-Setup-Subpanel-Lifting",
+    async fn test_retry_with_new_token_on_unauthorized() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/oauth2/token"))
+            .and(body_string_contains("grant_type=password"))
+            .respond_with(json_response("token.json"))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/oauth2/token"))
+            .and(body_string_contains("grant_type=refresh_token"))
+            .respond_with(json_response("token_refreshed.json"))
+            .expect(1)
+            .mount(&server)
+            .await;
+        // The first token has been revoked on the server side
+        Mock::given(method("GET"))
+            .and(path("/v1/tags"))
+            .and(header(
+                "authorization",
+                "Bearer 3a8f1c0e2d4b6a7988e1f0c2d3b4a5968778695a",
+            ))
+            .respond_with(ResponseTemplate::new(401))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/tags"))
+            .and(header(
+                "authorization",
+                "Bearer 9d0e1f2a3b4c5d6e7f8091a2b3c4d5e6f7a8b9c0",
+            ))
+            .respond_with(json_response("list_tags.json"))
+            .mount(&server)
+            .await;
+        let cc_client = client_for(&server).await;
+
+        assert_eq!(cc_client.find_tag_by_name("de").await.unwrap(), 5502);
+    }
+
+    /// Runs a request with a cache holding a token issued to `username`, and expects to log in
+    /// `logins` times.
+    async fn request_with_cached_token(username: &str, logins: u64) {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/oauth2/token"))
+            .respond_with(json_response("token.json"))
+            .expect(logins)
+            .mount(&server)
+            .await;
+        mock_list(&server, "tags", "list_tags.json").await;
+        let token_cache = std::env::temp_dir().join(format!(
+            "coffeecup-client-token-{username}-{}.json",
+            std::process::id()
+        ));
+        let config = CoffeeCupConfig::new(&format!("{}/v1", server.uri()), &server.uri()).unwrap();
+        write_cache(
+            &token_cache,
+            &CachedToken {
+                access_token: "3a8f1c0e2d4b6a7988e1f0c2d3b4a5968778695a".to_string(),
+                refresh_token: None,
+                expires_at: Some(Utc::now() + TimeDelta::hours(1)),
+                token_url: config.token_url().unwrap().to_string(),
+                client_id: "client_id".to_string(),
+                username: Some(username.to_string()),
+            },
+        )
+        .unwrap();
+
+        let cc_client = CoffeeCup::new_with_config(
+            config,
+            Credentials::password("user@example.com", "secret"),
+            Some(token_cache.clone()),
         )
         .await
         .unwrap();
+        let tag_id = cc_client.find_tag_by_name("de").await;
+        std::fs::remove_file(&token_cache).unwrap();
+
+        assert_eq!(tag_id.unwrap(), 5502);
+    }
+
+    #[tokio::test]
+    async fn test_reuse_cached_token() {
+        request_with_cached_token("user@example.com", 0).await;
+    }
+
+    #[tokio::test]
+    async fn test_ignore_cached_token_of_other_user() {
+        request_with_cached_token("other@example.com", 1).await;
+    }
+
+    #[tokio::test]
+    async fn test_list_projects_follows_pages() {
+        let server = mock_server().await;
+        mock_projects(&server).await;
+        let cc_client = client_for(&server).await;
+
+        let projects = cc_client.list_projects().await.unwrap();
+
+        let ids: Vec<usize> = projects.iter().map(|project| project.id).collect();
+        assert_eq!(ids, vec![10, 11, 12]);
+    }
+
+    #[tokio::test]
+    async fn test_list_customer() {
+        let server = mock_server().await;
+        mock_list(&server, "clients", "list_clients.json").await;
+        let cc_client = client_for(&server).await;
+
+        let customers = cc_client.list_customer().await.unwrap();
+
+        assert_eq!(customers.len(), 2);
+        assert_eq!(customers[1].name, "Stackable");
+    }
+
+    #[tokio::test]
+    async fn test_get_tag() {
+        let server = mock_server().await;
+        mock_list(&server, "tags", "list_tags.json").await;
+        let cc_client = client_for(&server).await;
+
+        assert_eq!(cc_client.find_tag_by_name("de").await.unwrap(), 5502);
+        assert!(matches!(
+            cc_client.find_tag_by_name("fr").await,
+            Err(Error::NoTag { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_get_project_ids_by_tag() {
+        let server = mock_server().await;
+        mock_list(&server, "tags", "list_tags.json").await;
+        Mock::given(method("GET"))
+            .and(path("/v1/tagassignments"))
+            .and(query_param("where", r#"{"model":"project","tag":5502}"#))
+            .and(header(
+                "authorization",
+                "Bearer 3a8f1c0e2d4b6a7988e1f0c2d3b4a5968778695a",
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "tagAssignments": [
+                    { "id": 1, "record": 10, "model": "project", "tag": 5502 },
+                    { "id": 2, "record": 11, "model": "project", "tag": 5502 }
+                ],
+                "meta": { "skip": 0, "limit": 500, "total": 2 }
+            })))
+            .mount(&server)
+            .await;
+        let cc_client = client_for(&server).await;
+
+        let project_ids = cc_client.get_project_ids_by_tag("de").await.unwrap();
+
+        assert_eq!(project_ids, vec![10, 11]);
+    }
+
+    #[tokio::test]
+    async fn test_create_update_and_delete_tag() {
+        let server = mock_server().await;
+        let tag = json!({
+            "tag": {
+                "id": 5504,
+                "label": "at",
+                "description": "Austria",
+                "type": null,
+                "status": 1,
+                "category": null
+            }
+        });
+        Mock::given(method("POST"))
+            .and(path("/v1/tags"))
+            .and(body_json(json!({ "tag": { "label": "at" } })))
+            .respond_with(ResponseTemplate::new(201).set_body_json(&tag))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("/v1/tags/5504"))
+            .and(body_partial_json(
+                json!({ "tag": { "description": "Austria" } }),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(&tag))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("DELETE"))
+            .and(path("/v1/tags/5504"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let cc_client = client_for(&server).await;
+
+        let created = cc_client.create_tag(&TagFields::new("at")).await.unwrap();
+        assert_eq!(created.id, 5504);
+
+        let fields = TagFields {
+            description: Some("Austria".to_string()),
+            ..TagFields::new("at")
+        };
+        let updated = cc_client.update_tag(5504, &fields).await.unwrap();
+        assert_eq!(updated.description.as_deref(), Some("Austria"));
+
+        cc_client.delete_tag(5504).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_assign_and_unassign_tag() {
+        let server = mock_server().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/tagassignments"))
+            .and(body_json(json!({
+                "tagAssignment": { "tag": 5502, "model": "timeEntry", "record": 9120003 }
+            })))
+            .respond_with(ResponseTemplate::new(201).set_body_json(json!({
+                "tagAssignment": { "id": 7, "tag": 5502, "model": "timeEntry", "record": 9120003 }
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("DELETE"))
+            .and(path("/v1/tagassignments/7"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let cc_client = client_for(&server).await;
+
+        let assignment = cc_client
+            .assign_tag(5502, TagModel::TimeEntry, 9120003)
+            .await
+            .unwrap();
+        assert_eq!(assignment.id, 7);
+        assert!(assignment.is_on(TagModel::TimeEntry, 9120003));
+
+        cc_client.delete_tag_assignment(7).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_get_tags_for_record() {
+        let server = mock_server().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/tagassignments"))
+            .and(query_param("where", r#"{"model":"project","record":12}"#))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "tagAssignments": [{ "id": 3, "record": 12, "model": "project", "tag": 5503 }],
+                "meta": { "skip": 0, "limit": 500, "total": 1 }
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/tagassignments"))
+            .and(query_param("where", r#"{"model":"project","record":1}"#))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "tagAssignments": [],
+                "meta": { "skip": 0, "limit": 500, "total": 0 }
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/tags"))
+            .and(query_param("where", r#"{"id":{"in":[5503]}}"#))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "tags": [{ "id": 5503, "label": "ch", "status": 1 }],
+                "meta": { "skip": 0, "limit": 500, "total": 1 }
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let cc_client = client_for(&server).await;
+
+        let tags = cc_client
+            .get_tags_for_record(TagModel::Project, 12)
+            .await
+            .unwrap();
+        assert_eq!(tags.len(), 1);
+        assert_eq!(tags[0].label, "ch");
+
+        // Records without tags don't need the tags to be looked up
+        assert!(cc_client
+            .get_tags_for_record(TagModel::Project, 1)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_tag_assignment_filter_where_clause() {
+        assert_eq!(
+            TagAssignmentFilter::tag(5502, TagModel::Client).where_clause(),
+            json!({ "tag": 5502, "model": "client" })
+        );
+        assert_eq!(
+            TagAssignmentFilter::record(TagModel::TimeEntry, 9120003).where_clause(),
+            json!({ "model": "timeEntry", "record": 9120003 })
+        );
+        assert_eq!(TagAssignmentFilter::default().where_clause(), json!({}));
+    }
+
+    #[tokio::test]
+    async fn test_get_my_projects() {
+        let server = mock_server().await;
+        mock_projects(&server).await;
+        mock_list(&server, "tasks", "list_tasks.json").await;
+        mock_list(&server, "taskassignments", "list_taskassignments.json").await;
+        mock_list(&server, "clients", "list_clients.json").await;
+        mock_list(&server, "tags", "list_tags.json").await;
+        Mock::given(method("GET"))
+            .and(path("/v1/tagassignments"))
+            .and(query_param("where", r#"{"model":"project"}"#))
+            .respond_with(json_response("list_tagassignments.json"))
+            .mount(&server)
+            .await;
+        let cc_client = client_for(&server).await;
+
+        let catalog = cc_client.get_my_projects().await.unwrap();
+
+        assert_eq!(catalog.len(), 3);
+        let entry = &catalog.by_code("001105")[0];
+        let task_labels: Vec<&str> = entry
+            .project
+            .tasks
+            .as_ref()
+            .unwrap()
+            .iter()
+            .map(|task| task.label.as_str())
+            .collect();
+        assert_eq!(task_labels, vec!["Consulting", "Support"]);
+        assert_eq!(entry.customer.as_ref().unwrap().name, "Company A");
+        assert_eq!(catalog.by_tag("ch")[0].project.id, 12);
+    }
+
+    #[tokio::test]
+    async fn test_create_project() {
+        let server = mock_server().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/projects"))
+            .and(body_json(json!({
+                "project": {
+                    "name": "Trino Rollout",
+                    "code": "001107",
+                    "client": 1,
+                    "billBy": 1,
+                    "hourlyRate": 120.0,
+                    "budgetBy": 1,
+                    "budgetHours": 40.0,
+                    "startDate": "2025-07-01",
+                    "customField1": "FJ-2025-0042"
+                }
+            })))
+            .respond_with(json_response("get_project.json"))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let cc_client = client_for(&server).await;
+
+        let project = NewProject::new("Trino Rollout")
+            .code("001107")
+            .client(1)
+            .billing(BillBy::Project, Some(120.0))
+            .budget(Budget::Hours(40.0))
+            .dates(NaiveDate::from_ymd_opt(2025, 7, 1).unwrap(), None)
+            .custom_field(1, "FJ-2025-0042")
+            .unwrap();
+        let created = cc_client.create_project(&project).await.unwrap();
+
+        assert_eq!(created.id, 13);
+        assert_eq!(created.budgetHours, 40.0);
+    }
+
+    #[test]
+    fn test_new_project_budget_by_fees() {
+        let project = NewProject::new("Support").budget(Budget::Fees(5000.0));
+
+        assert_eq!(
+            serde_json::to_value(&project).unwrap(),
+            json!({ "name": "Support", "budgetBy": 2, "budget": 5000.0 })
+        );
+        assert_eq!(
+            serde_json::to_value(NewProject::new("Support")).unwrap(),
+            json!({ "name": "Support" })
+        );
+    }
+
+    #[test]
+    fn test_new_project_custom_fields() {
+        let project = NewProject::new("Support")
+            .custom_field(10, "FJ-2025-0042")
+            .unwrap();
+        assert_eq!(
+            serde_json::to_value(&project).unwrap(),
+            json!({ "name": "Support", "customField10": "FJ-2025-0042" })
+        );
+
+        assert!(matches!(
+            NewProject::new("Support").custom_field(0, "x"),
+            Err(Error::InvalidCustomField { number: 0 })
+        ));
+        assert!(matches!(
+            NewProject::new("Support").custom_field(11, "x"),
+            Err(Error::InvalidCustomField { number: 11 })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_archive_and_complete_project() {
+        let server = mock_server().await;
+        Mock::given(method("PUT"))
+            .and(path("/v1/projects/13"))
+            .and(body_json(json!({ "project": { "status": 0 } })))
+            .respond_with(json_response("get_project.json"))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("/v1/projects/13"))
+            .and(body_json(
+                json!({ "project": { "completedAt": "2025-12-31" } }),
+            ))
+            .respond_with(json_response("get_project.json"))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let cc_client = client_for(&server).await;
+
+        cc_client.archive_project(13).await.unwrap();
+        cc_client
+            .complete_project(13, NaiveDate::from_ymd_opt(2025, 12, 31).unwrap())
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_create_and_assign_task() {
+        let server = mock_server().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/tasks"))
+            .and(body_json(json!({ "task": { "label": "Training" } })))
+            .respond_with(ResponseTemplate::new(201).set_body_json(json!({
+                "task": { "id": 213, "label": "Training" }
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/taskassignments"))
+            .and(body_json(
+                json!({ "taskAssignment": { "project": 13, "task": 213 } }),
+            ))
+            .respond_with(ResponseTemplate::new(201).set_body_json(json!({
+                "taskAssignment": { "id": 6, "project": 13, "task": 213 }
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let cc_client = client_for(&server).await;
+
+        let task = cc_client.create_task("Training").await.unwrap();
+        let assignment = cc_client.assign_task(13, task.id).await.unwrap();
+
+        assert_eq!(assignment.id, 6);
+        assert_eq!(assignment.task, 213);
+    }
+
+    #[tokio::test]
+    async fn test_get_user() {
+        let server = mock_server().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/users/43"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "user": {
+                    "id": 43,
+                    "firstname": "John",
+                    "lastname": "Roe",
+                    "email": "john.roe@stackable.tech"
+                }
+            })))
+            .mount(&server)
+            .await;
+        let cc_client = client_for(&server).await;
+
+        let user = cc_client.get_user(43).await.unwrap();
+
+        assert_eq!(user.email, "john.roe@stackable.tech");
+    }
+
+    #[tokio::test]
+    async fn test_find_user_by_email() {
+        let server = mock_server().await;
+        mock_list(&server, "users", "list_users.json").await;
+        let cc_client = client_for(&server).await;
+
+        let user = cc_client
+            .find_user_by_email("Jane.Doe@stackable.tech ")
+            .await
+            .unwrap();
+        assert_eq!(user.map(|user| user.id), Some(42));
+
+        let user = cc_client
+            .find_user_by_email("max.mustermann@stackable.tech")
+            .await
+            .unwrap();
+        assert!(user.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_get_team_members() {
+        let server = mock_server().await;
+        mock_list(&server, "users", "list_users.json").await;
+        Mock::given(method("GET"))
+            .and(path("/v1/teammemberships"))
+            .and(query_param("where", r#"{"team":4}"#))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "teamMemberships": [{ "id": 2, "team": 4, "user": 43 }],
+                "meta": { "skip": 0, "limit": 500, "total": 1 }
+            })))
+            .mount(&server)
+            .await;
+        let cc_client = client_for(&server).await;
+
+        let members = cc_client.get_team_members(4).await.unwrap();
+
+        assert_eq!(members.len(), 1);
+        assert_eq!(members[0].firstname, "John");
+    }
+
+    #[tokio::test]
+    async fn test_get_timeentries() {
+        let server = mock_server().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/timeentries"))
+            .and(query_param("where", r#"{"user":42}"#))
+            .respond_with(json_response("list_timeentries.json"))
+            .mount(&server)
+            .await;
+        let cc_client = client_for(&server).await;
+
+        let entries = cc_client
+            .get_timeentries(&TimeEntryFilter {
+                user: Some(42),
+                ..TimeEntryFilter::default()
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].duration, 5400);
+    }
+
+    #[tokio::test]
+    async fn test_create_timeentry() {
+        let server = mock_server().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/timeentries"))
+            .and(body_partial_json(json!({
+                "timeEntry": { "day": "2025-06-02", "duration": 1800, "project": 10 }
+            })))
+            .respond_with(timeentry_response(201))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let cc_client = client_for(&server).await;
+        let entry = resolver()
+            .to_timeentries(bdwh_entry(), &ConversionPolicy::default())
+            .unwrap()
+            .remove(0);
+
+        let created = cc_client.create_timeentry(&entry).await.unwrap();
+
+        assert_eq!(created.id, Some(9120003));
+    }
+
+    #[test]
+    fn test_new_timeentry_leaves_out_server_fields() {
+        let entry = resolver()
+            .to_timeentries(bdwh_entry(), &ConversionPolicy::default())
+            .unwrap()
+            .remove(0);
+
+        let body = serde_json::to_value(TimeEntryWrapper { timeEntry: entry }).unwrap();
+
+        assert!(body["timeEntry"].get("id").is_none());
+        assert!(body["timeEntry"].get("durationRounded").is_none());
+        assert_eq!(body["timeEntry"]["team"], 3);
+    }
+
+    #[tokio::test]
+    async fn test_create_timeentries_reports_every_entry() {
+        let server = mock_server().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/timeentries"))
+            .and(body_partial_json(
+                json!({ "timeEntry": { "day": "2025-06-03" } }),
+            ))
+            .respond_with(ResponseTemplate::new(400))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/timeentries"))
+            .respond_with(timeentry_response(201))
+            .mount(&server)
+            .await;
+        let cc_client = client_for(&server).await;
+        let mut failing = bdwh_entry();
+        failing.date = NaiveDate::from_ymd_opt(2025, 6, 3).unwrap();
+        let mut entries = Vec::new();
+        for row in [bdwh_entry(), failing, bdwh_entry()] {
+            entries.extend(
+                resolver()
+                    .to_timeentries(row, &ConversionPolicy::default())
+                    .unwrap(),
+            );
+        }
+
+        let results = cc_client.create_timeentries(&entries).await;
+
+        assert_eq!(results.len(), 3);
+        assert!(results[0].is_ok());
+        assert!(matches!(results[1], Err(Error::Status { .. })));
+        assert!(results[2].is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_get_update_and_delete_timeentry() {
+        let server = mock_server().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/timeentries/9120003"))
+            .respond_with(timeentry_response(200))
+            .mount(&server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("/v1/timeentries/9120003"))
+            .and(body_partial_json(
+                json!({ "timeEntry": { "duration": 3600 } }),
+            ))
+            .and(|request: &Request| {
+                let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+                [
+                    "id",
+                    "createdAt",
+                    "updatedAt",
+                    "durationRounded",
+                    "hourlyRate",
+                ]
+                .iter()
+                .all(|field| body["timeEntry"].get(field).is_none())
+            })
+            .respond_with(timeentry_response(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("DELETE"))
+            .and(path("/v1/timeentries/9120003"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let cc_client = client_for(&server).await;
+
+        let mut entry = cc_client.get_timeentry(9120003).await.unwrap();
+        assert_eq!(entry.duration, 1800);
+        assert_eq!(entry.billable, Some(true));
+
+        entry.duration = 3600;
+        cc_client.update_timeentry(9120003, &entry).await.unwrap();
+        cc_client.delete_timeentry(9120003).await.unwrap();
+    }
+
+    #[test]
+    fn test_unknown_tracking_type() {
+        let body = fixture("get_timeentry.json").replace("\"WORK\"", "\"BREAK\"");
+
+        let response: TimeEntryWrapper = serde_json::from_str(&body).unwrap();
+
+        assert!(matches!(
+            response.timeEntry.trackingType,
+            TrackingType::Other
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_error_status_carries_body() {
+        let server = mock_server().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/timeentries"))
+            .respond_with(
+                ResponseTemplate::new(400).set_body_string(r#"{"error":"task not in project"}"#),
+            )
+            .mount(&server)
+            .await;
+        let cc_client = client_for(&server).await;
+        let entry = resolver()
+            .to_timeentries(bdwh_entry(), &ConversionPolicy::default())
+            .unwrap()
+            .remove(0);
+
+        let result = cc_client.create_timeentry(&entry).await;
+
+        match result {
+            Err(Error::Status { status, body, .. }) => {
+                assert_eq!(status, StatusCode::BAD_REQUEST);
+                assert_eq!(body, r#"{"error":"task not in project"}"#);
+            }
+            other => panic!("Expected status error, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_error_decode_carries_payload() {
+        let server = mock_server().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/clients"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("<html>maintenance</html>"))
+            .mount(&server)
+            .await;
+        let cc_client = client_for(&server).await;
+
+        let result = cc_client.list_customer().await;
+
+        match result {
+            Err(Error::DecodeJson { body, .. }) => assert_eq!(body, "<html>maintenance</html>"),
+            other => panic!("Expected decode error, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_error_unauthorized_after_refresh() {
+        let server = mock_server().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/tags"))
+            .respond_with(ResponseTemplate::new(401).set_body_string("invalid token"))
+            .expect(2)
+            .mount(&server)
+            .await;
+        let cc_client = client_for(&server).await;
+
+        let result = cc_client.find_tag_by_name("de").await;
+
+        assert!(matches!(result, Err(Error::Unauthorized { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_error_login_rejected() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/oauth2/token"))
+            .respond_with(ResponseTemplate::new(400).set_body_raw(
+                r#"{"error":"invalid_grant","error_description":"Invalid user credentials"}"#,
+                "application/json",
+            ))
+            .mount(&server)
+            .await;
+        let config = CoffeeCupConfig::new(&format!("{}/v1", server.uri()), &server.uri()).unwrap();
+
+        let result = CoffeeCup::new_with_config(
+            config,
+            Credentials::password("user@example.com", "wrong"),
+            None,
+        )
+        .await;
 
-        println!("test");
-        let tag_id = cc_client.find_tag_by_name("de").await.unwrap();
-        println!("{:?}", tag_id)
+        assert!(matches!(result, Err(Error::RequestToken { .. })));
     }
 }
@@ -0,0 +1,106 @@
+use crate::coffeecup::client::{Error, ParseUrlSnafu};
+use reqwest::Url;
+use snafu::ResultExt;
+
+const DEFAULT_BASE_URL: &str = "https://api.coffeecup.app/v1/";
+const DEFAULT_COMPANY_URL: &str = "https://stackable.coffeecup.app";
+
+/// Tells the [`crate::coffeecup::client::CoffeeCup`] client where to find the API.
+///
+/// The REST API is shared between all CoffeeCup tenants, while tokens and exports are served
+/// by the tenant (`company_url`) itself. Pointing both at a local server allows running the
+/// client against a mock.
+#[derive(Debug, Clone)]
+pub struct CoffeeCupConfig {
+    /// Url all endpoints are resolved against, for example `https://api.coffeecup.app/v1/`
+    pub base_url: Url,
+    /// Url of the company tenant, for example `https://stackable.coffeecup.app`
+    pub company_url: Url,
+}
+
+impl Default for CoffeeCupConfig {
+    fn default() -> Self {
+        Self::new(DEFAULT_BASE_URL, DEFAULT_COMPANY_URL)
+            .expect("default CoffeeCup urls should be valid")
+    }
+}
+
+impl CoffeeCupConfig {
+    pub fn new(base_url: &str, company_url: &str) -> Result<Self, Error> {
+        // Url::join drops the last path segment unless the base ends with a slash, which would
+        // turn `.../v1` + `projects` into `.../projects`
+        let base_url = if base_url.ends_with('/') {
+            base_url.to_string()
+        } else {
+            format!("{base_url}/")
+        };
+
+        Ok(Self {
+            base_url: Url::parse(&base_url).context(ParseUrlSnafu { url_type: "base" })?,
+            company_url: Url::parse(company_url).context(ParseUrlSnafu {
+                url_type: "company",
+            })?,
+        })
+    }
+
+    /// Returns the url of the API endpoint `path`, for example `projects`.
+    pub fn endpoint(&self, path: &str) -> Result<Url, Error> {
+        self.base_url
+            .join(path)
+            .context(ParseUrlSnafu { url_type: path })
+    }
+
+    /// Returns the url the tenant renders exports of `path` at, for example `timeEntries`.
+    pub fn export_url(&self, path: &str) -> Result<Url, Error> {
+        self.company_url
+            .join(&format!("v1/export/{path}"))
+            .context(ParseUrlSnafu { url_type: "export" })
+    }
+
+    /// Returns the url the tenant issues OAuth2 tokens at.
+    pub fn token_url(&self) -> Result<Url, Error> {
+        let mut token_url = self
+            .company_url
+            .join("oauth2/token")
+            .context(ParseUrlSnafu { url_type: "token" })?;
+        token_url.query_pairs_mut().append_pair(
+            "companyurl",
+            self.company_url.as_str().trim_end_matches('/'),
+        );
+        Ok(token_url)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::coffeecup::config::CoffeeCupConfig;
+
+    #[test]
+    fn test_default_urls() {
+        let config = CoffeeCupConfig::default();
+
+        assert_eq!(
+            config.endpoint("projects").unwrap().as_str(),
+            "https://api.coffeecup.app/v1/projects"
+        );
+        assert_eq!(
+            config.token_url().unwrap().as_str(),
+            "https://stackable.coffeecup.app/oauth2/token?companyurl=https%3A%2F%2Fstackable.coffeecup.app"
+        );
+        assert_eq!(
+            config.export_url("timeEntries").unwrap().as_str(),
+            "https://stackable.coffeecup.app/v1/export/timeEntries"
+        );
+    }
+
+    #[test]
+    fn test_base_url_without_trailing_slash() {
+        let config =
+            CoffeeCupConfig::new("http://127.0.0.1:8080/v1", "http://127.0.0.1:8080").unwrap();
+
+        assert_eq!(
+            config.endpoint("tags").unwrap().as_str(),
+            "http://127.0.0.1:8080/v1/tags"
+        );
+    }
+}
@@ -0,0 +1,503 @@
+use crate::bdwh::structs::TimeEntry as BdwhTimeEntry;
+use crate::coffeecup::structs::ValidationStatus;
+use chrono::{NaiveTime, TimeDelta};
+use serde::Deserialize;
+use snafu::{ensure, Snafu};
+
+const MILLIS_PER_HOUR: f64 = 3_600_000.0;
+const MILLIS_PER_MINUTE: i64 = 60_000;
+
+/// Appended to the external ID of the non-billable part of a split row.
+pub const NON_BILLABLE_SUFFIX: &str = "-nb";
+
+#[derive(Snafu, Debug)]
+pub enum Error {
+    #[snafu(display("Row has negative hours, {total}h in total and {billable}h billable"))]
+    NegativeHours { total: f64, billable: f64 },
+    #[snafu(display(
+        "Row is only partly billable, {billable}h of {total}h, which can't be flagged; split it instead"
+    ))]
+    PartlyBillable { total: f64, billable: f64 },
+}
+
+/// Which of the hours booked in BDWH become the duration in CoffeeCup.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HoursField {
+    #[default]
+    Total,
+    Billable,
+}
+
+/// How the billable hours of BDWH rows show up in CoffeeCup.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Billing {
+    /// Leaves the billable flag to CoffeeCup, which takes it from the task
+    #[default]
+    Ignore,
+    /// Marks entries of rows that are billable in full as billable and rows without billable
+    /// hours as non-billable. Partly billable rows are rejected, as neither flag would be right
+    Flag,
+    /// Splits rows into a billable entry with the billable hours and a non-billable entry with
+    /// the rest, `hours` is not used then
+    Split,
+}
+
+/// How durations are rounded.
+///
+/// ```toml
+/// rounding = { mode = "up", minutes = 15 }
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(tag = "mode", rename_all = "lowercase")]
+pub enum Rounding {
+    /// Keeps the duration as booked, to the second
+    #[default]
+    Exact,
+    /// Rounds down to a multiple of `minutes`
+    Floor { minutes: u32 },
+    /// Rounds to the closest multiple of `minutes`, halfway durations are rounded up
+    Nearest { minutes: u32 },
+    /// Rounds up to a multiple of `minutes`
+    Up { minutes: u32 },
+}
+
+/// Part of a BDWH row that becomes one CoffeeCup entry.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Portion {
+    /// Duration in seconds
+    pub duration: usize,
+    pub billable: Option<bool>,
+    pub start_time: Option<NaiveTime>,
+    pub end_time: Option<NaiveTime>,
+    /// Recognizes the CoffeeCup entry when migrating again
+    pub external_id: String,
+}
+
+/// Decides how a BDWH row is turned into a CoffeeCup entry.
+///
+/// The defaults keep the total hours to the second, drop the times of day and create entries
+/// that are approved already, which is how entries have been migrated so far.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ConversionPolicy {
+    pub hours: HoursField,
+    pub billing: Billing,
+    /// Applies to every entry on its own, if a row is split
+    pub rounding: Rounding,
+    /// Sets start and end time of the CoffeeCup entry from BDWH
+    pub carry_times: bool,
+    pub validation_status: ValidationStatus,
+}
+
+impl Default for ConversionPolicy {
+    fn default() -> Self {
+        Self {
+            hours: HoursField::default(),
+            billing: Billing::default(),
+            rounding: Rounding::default(),
+            carry_times: false,
+            validation_status: ValidationStatus::APPROVED,
+        }
+    }
+}
+
+impl ConversionPolicy {
+    /// Returns the parts `entry` is converted into, usually one.
+    ///
+    /// Split rows become a billable part, which keeps the fingerprint of the row as external
+    /// ID, and a non-billable part with [`NON_BILLABLE_SUFFIX`] appended. Parts that are left
+    /// without any time after rounding are dropped. Rows with negative hours are rejected, and
+    /// so are partly billable rows if they are to be flagged.
+    ///
+    /// If times are carried and the row has them, a row that is converted as it is keeps its
+    /// own times. Otherwise the parts are laid out back to back from the start of the row,
+    /// and lose their times if that would run past midnight.
+    pub fn portions(&self, entry: &BdwhTimeEntry) -> Result<Vec<Portion>, Error> {
+        ensure!(
+            entry.total_time >= 0.0 && entry.billable_time >= 0.0,
+            NegativeHoursSnafu {
+                total: entry.total_time,
+                billable: entry.billable_time,
+            }
+        );
+        let total = to_millis(entry.total_time);
+        let billable = to_millis(entry.billable_time).min(total);
+        let hours = match self.hours {
+            HoursField::Total => total,
+            HoursField::Billable => billable,
+        };
+        let fingerprint = entry.fingerprint();
+        let parts = match self.billing {
+            Billing::Ignore => vec![(hours, None, fingerprint)],
+            Billing::Flag => {
+                ensure!(
+                    billable == 0 || billable == total,
+                    PartlyBillableSnafu {
+                        total: entry.total_time,
+                        billable: entry.billable_time,
+                    }
+                );
+                vec![(hours, Some(total > 0 && billable == total), fingerprint)]
+            }
+            Billing::Split => {
+                let non_billable = format!("{fingerprint}{NON_BILLABLE_SUFFIX}");
+                vec![
+                    (billable, Some(true), fingerprint),
+                    (total - billable, Some(false), non_billable),
+                ]
+            }
+        };
+        let parts: Vec<(usize, i64, Option<bool>, String)> = parts
+            .into_iter()
+            .map(|(millis, billable, external_id)| {
+                (self.round_millis(millis), millis, billable, external_id)
+            })
+            .filter(|(duration, ..)| *duration > 0)
+            .collect();
+
+        let as_booked = self.rounding == Rounding::Exact
+            && matches!(parts.as_slice(), [(_, millis, ..)] if *millis == total);
+        let times = match (
+            entry.start_time.filter(|_| self.carry_times),
+            entry.end_time,
+        ) {
+            (Some(start_time), Some(end_time)) if as_booked => {
+                (start_time <= end_time).then(|| vec![(start_time, end_time)])
+            }
+            (Some(start_time), _) => lay_out(start_time, parts.iter().map(|part| part.0)),
+            (None, _) => None,
+        };
+
+        Ok(parts
+            .into_iter()
+            .enumerate()
+            .map(|(index, (duration, _, billable, external_id))| {
+                let times = times.as_ref().map(|times| times[index]);
+                Portion {
+                    duration,
+                    billable,
+                    start_time: times.map(|(start_time, _)| start_time),
+                    end_time: times.map(|(_, end_time)| end_time),
+                    external_id,
+                }
+            })
+            .collect())
+    }
+
+    /// Converts milliseconds to seconds, rounded as configured.
+    fn round_millis(&self, millis: i64) -> usize {
+        let millis = match self.rounding {
+            Rounding::Exact => millis,
+            Rounding::Floor { minutes } => round(millis, minutes, Direction::Down),
+            Rounding::Nearest { minutes } => round(millis, minutes, Direction::Nearest),
+            Rounding::Up { minutes } => round(millis, minutes, Direction::Up),
+        };
+        ((millis + 500) / 1000) as usize
+    }
+}
+
+/// Which way [`round`] moves durations that are not a multiple of the unit.
+#[derive(Debug, Clone, Copy)]
+enum Direction {
+    Down,
+    /// Halfway durations are rounded up
+    Nearest,
+    Up,
+}
+
+/// Converts hours to whole milliseconds, so that binary floating point errors like
+/// `0.1 * 3600.0 = 360.00000000000006` don't leak into durations.
+fn to_millis(hours: f64) -> i64 {
+    (hours * MILLIS_PER_HOUR).round() as i64
+}
+
+/// Rounds `millis` to a multiple of `minutes`.
+fn round(millis: i64, minutes: u32, direction: Direction) -> i64 {
+    let unit = i64::from(minutes) * MILLIS_PER_MINUTE;
+    if unit == 0 {
+        return millis;
+    }
+    let offset = match direction {
+        Direction::Down => 0,
+        Direction::Nearest => unit / 2,
+        // Must not move durations that are a multiple already
+        Direction::Up => unit - 1,
+    };
+    (millis + offset) / unit * unit
+}
+
+/// Returns start and end time of parts with `durations` (in seconds) booked back to back
+/// from `start_time`, unless they would run past midnight.
+fn lay_out(
+    mut start_time: NaiveTime,
+    durations: impl IntoIterator<Item = usize>,
+) -> Option<Vec<(NaiveTime, NaiveTime)>> {
+    let mut times = Vec::new();
+    for duration in durations {
+        let (end_time, wrapped) =
+            start_time.overflowing_add_signed(TimeDelta::seconds(duration as i64));
+        if wrapped != 0 {
+            return None;
+        }
+        times.push((start_time, end_time));
+        start_time = end_time;
+    }
+    Some(times)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::coffeecup::conversion::{
+        to_millis, Billing, ConversionPolicy, Error, HoursField, Portion, Rounding,
+    };
+    use crate::coffeecup::mock::bdwh_entry;
+    use crate::coffeecup::structs::ValidationStatus;
+    use chrono::NaiveTime;
+
+    fn round_hours(policy: &ConversionPolicy, hours: f64) -> usize {
+        policy.round_millis(to_millis(hours))
+    }
+
+    fn rounding(rounding: Rounding) -> ConversionPolicy {
+        ConversionPolicy {
+            rounding,
+            ..ConversionPolicy::default()
+        }
+    }
+
+    #[test]
+    fn test_exact_hours_are_precise() {
+        let policy = ConversionPolicy::default();
+
+        assert_eq!(round_hours(&policy, 0.1), 360);
+        assert_eq!(round_hours(&policy, 0.7), 2520);
+        // Truncating 4.35 * 3600.0 = 15659.999999999998 would lose a second
+        assert_eq!(round_hours(&policy, 4.35), 15660);
+        assert_eq!(round_hours(&policy, 1.0 / 3.0), 1200);
+        assert_eq!(round_hours(&policy, 0.0), 0);
+    }
+
+    #[test]
+    fn test_rounding_modes() {
+        // 0.1h are 6 minutes
+        assert_eq!(
+            round_hours(&rounding(Rounding::Floor { minutes: 15 }), 0.1),
+            0
+        );
+        assert_eq!(
+            round_hours(&rounding(Rounding::Nearest { minutes: 15 }), 0.1),
+            0
+        );
+        assert_eq!(
+            round_hours(&rounding(Rounding::Up { minutes: 15 }), 0.1),
+            900
+        );
+
+        // 0.125h are 7.5 minutes, halfway between 0 and 15
+        assert_eq!(
+            round_hours(&rounding(Rounding::Nearest { minutes: 15 }), 0.125),
+            900
+        );
+        assert_eq!(
+            round_hours(&rounding(Rounding::Floor { minutes: 15 }), 0.4),
+            900
+        );
+
+        // Multiples stay as they are, even if they are not exactly representable
+        assert_eq!(
+            round_hours(&rounding(Rounding::Up { minutes: 6 }), 0.1),
+            360
+        );
+        assert_eq!(
+            round_hours(&rounding(Rounding::Floor { minutes: 6 }), 0.3),
+            1080
+        );
+        assert_eq!(
+            round_hours(&rounding(Rounding::Up { minutes: 0 }), 0.1),
+            360
+        );
+    }
+
+    #[test]
+    fn test_hours_field_and_times() {
+        let mut entry = bdwh_entry();
+        entry.billable_time = 0.25;
+        let policy = ConversionPolicy {
+            hours: HoursField::Billable,
+            carry_times: true,
+            ..ConversionPolicy::default()
+        };
+
+        let portions = ConversionPolicy::default().portions(&entry).unwrap();
+        assert_eq!(
+            portions,
+            vec![Portion {
+                duration: 1800,
+                billable: None,
+                start_time: None,
+                end_time: None,
+                external_id: entry.fingerprint(),
+            }]
+        );
+        let portions = policy.portions(&entry).unwrap();
+        assert_eq!(portions[0].duration, 900);
+        assert_eq!(portions[0].start_time, entry.start_time);
+        assert_eq!(portions[0].end_time, NaiveTime::from_hms_opt(9, 15, 0));
+
+        // Rows without times don't get any
+        entry.start_time = None;
+        entry.end_time = None;
+        assert_eq!(policy.portions(&entry).unwrap()[0].end_time, None);
+    }
+
+    #[test]
+    fn test_times_past_midnight() {
+        let mut entry = bdwh_entry();
+        entry.start_time = NaiveTime::from_hms_opt(9, 0, 0);
+        // The row has a 10 minute break
+        entry.end_time = NaiveTime::from_hms_opt(9, 40, 0);
+        let policy = ConversionPolicy {
+            carry_times: true,
+            ..ConversionPolicy::default()
+        };
+
+        let portions = policy.portions(&entry).unwrap();
+        assert_eq!(portions[0].duration, 1800);
+        assert_eq!(portions[0].end_time, entry.end_time);
+
+        entry.start_time = NaiveTime::from_hms_opt(23, 45, 0);
+        entry.end_time = NaiveTime::from_hms_opt(0, 15, 0);
+        assert_eq!(policy.portions(&entry).unwrap()[0].start_time, None);
+
+        let policy = ConversionPolicy {
+            rounding: Rounding::Up { minutes: 60 },
+            ..policy
+        };
+        entry.end_time = NaiveTime::from_hms_opt(23, 59, 0);
+        entry.total_time = 0.2;
+        let portions = policy.portions(&entry).unwrap();
+        assert_eq!(portions[0].duration, 3600);
+        assert_eq!(portions[0].start_time, None);
+        assert_eq!(portions[0].end_time, None);
+    }
+
+    #[test]
+    fn test_parts_without_time_are_dropped() {
+        let mut entry = bdwh_entry();
+        entry.billable_time = 0.0;
+        let billable_hours = ConversionPolicy {
+            hours: HoursField::Billable,
+            ..ConversionPolicy::default()
+        };
+        assert!(billable_hours.portions(&entry).unwrap().is_empty());
+        let flag = ConversionPolicy {
+            billing: Billing::Flag,
+            ..billable_hours
+        };
+        assert!(flag.portions(&entry).unwrap().is_empty());
+
+        entry.total_time = 0.0;
+        assert!(ConversionPolicy::default()
+            .portions(&entry)
+            .unwrap()
+            .is_empty());
+
+        // 0.1h are 6 minutes
+        entry.total_time = 0.1;
+        assert!(rounding(Rounding::Floor { minutes: 15 })
+            .portions(&entry)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_negative_hours_are_rejected() {
+        let mut entry = bdwh_entry();
+        entry.total_time = -0.5;
+
+        assert!(matches!(
+            ConversionPolicy::default().portions(&entry),
+            Err(Error::NegativeHours { total, .. }) if total == -0.5
+        ));
+
+        entry.total_time = 0.5;
+        entry.billable_time = -0.5;
+        assert!(ConversionPolicy::default().portions(&entry).is_err());
+    }
+
+    #[test]
+    fn test_billing_flag() {
+        let policy = ConversionPolicy {
+            billing: Billing::Flag,
+            ..ConversionPolicy::default()
+        };
+        let mut entry = bdwh_entry();
+
+        assert_eq!(policy.portions(&entry).unwrap()[0].billable, Some(true));
+        entry.billable_time = 0.0;
+        let portions = policy.portions(&entry).unwrap();
+        assert_eq!(portions[0].billable, Some(false));
+        assert_eq!(portions[0].duration, 1800);
+
+        entry.billable_time = 0.3;
+        assert!(matches!(
+            policy.portions(&entry),
+            Err(Error::PartlyBillable { total, billable }) if total == 0.5 && billable == 0.3
+        ));
+    }
+
+    #[test]
+    fn test_billing_split() {
+        let policy = ConversionPolicy {
+            billing: Billing::Split,
+            carry_times: true,
+            ..ConversionPolicy::default()
+        };
+        let mut entry = bdwh_entry();
+        entry.total_time = 0.3;
+        entry.billable_time = 0.2;
+
+        let portions = policy.portions(&entry).unwrap();
+
+        assert_eq!(portions.len(), 2);
+        assert_eq!(portions[0].billable, Some(true));
+        assert_eq!(portions[0].duration, 720);
+        assert_eq!(portions[0].external_id, entry.fingerprint());
+        assert_eq!(portions[1].billable, Some(false));
+        // 0.3 - 0.2 = 0.09999999999999998
+        assert_eq!(portions[1].duration, 360);
+        assert_eq!(
+            portions[1].external_id,
+            format!("{}-nb", entry.fingerprint())
+        );
+        assert_eq!(portions[1].start_time, portions[0].end_time);
+        assert_eq!(portions[1].end_time, NaiveTime::from_hms_opt(9, 18, 0));
+
+        // Fully billable rows keep a single entry
+        entry.billable_time = 0.3;
+        let portions = policy.portions(&entry).unwrap();
+        assert_eq!(portions.len(), 1);
+        assert_eq!(portions[0].billable, Some(true));
+    }
+
+    #[test]
+    fn test_parse_policy() {
+        let policy: ConversionPolicy = toml::from_str(
+            r#"
+            hours = "billable"
+            billing = "split"
+            rounding = { mode = "up", minutes = 15 }
+            validation_status = "SUBMITTED"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(policy.hours, HoursField::Billable);
+        assert_eq!(policy.billing, Billing::Split);
+        assert_eq!(policy.rounding, Rounding::Up { minutes: 15 });
+        assert!(!policy.carry_times);
+        assert_eq!(policy.validation_status, ValidationStatus::SUBMITTED);
+    }
+}
@@ -0,0 +1,273 @@
+//! Hour overviews as CoffeeCup renders them for download, for example to attach them to
+//! invoices.
+
+use crate::dates::month_bounds;
+use chrono::NaiveDate;
+use strum_macros::{Display, EnumString};
+
+/// File format of an export.
+#[derive(Debug, Clone, Copy, Default, PartialEq, EnumString, Display)]
+#[strum(serialize_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Pdf,
+    Csv,
+    Xlsx,
+}
+
+/// How durations are written in an export.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum DurationFormat {
+    /// `1:30`
+    #[default]
+    HoursMinutes,
+    /// `1.50`
+    Decimal,
+}
+
+impl DurationFormat {
+    /// Returns the number CoffeeCup expects for the format.
+    fn code(self) -> u8 {
+        match self {
+            Self::HoursMinutes => 0,
+            Self::Decimal => 1,
+        }
+    }
+}
+
+/// A column that can be shown in an export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString, Display)]
+#[strum(serialize_all = "snake_case")]
+pub enum ExportColumn {
+    Client,
+    Project,
+    Task,
+    User,
+    Comment,
+    Team,
+    Reference,
+    Date,
+    /// Start and end time of every entry
+    StartEnd,
+}
+
+impl ExportColumn {
+    const ALL: [Self; 9] = [
+        Self::Client,
+        Self::Project,
+        Self::Task,
+        Self::User,
+        Self::Comment,
+        Self::Team,
+        Self::Reference,
+        Self::Date,
+        Self::StartEnd,
+    ];
+}
+
+/// What the entries of an export are grouped by.
+#[derive(Debug, Clone, Copy, PartialEq, EnumString, Display)]
+#[strum(serialize_all = "lowercase")]
+pub enum GroupBy {
+    Date,
+    Client,
+    Project,
+    Task,
+    User,
+}
+
+/// Describes an export of the time entries between two days, see
+/// [`crate::coffeecup::client::CoffeeCup::export_timeentries`].
+///
+/// The defaults match the hour overview of the CoffeeCup web UI: a PDF grouped by date with
+/// every column but start and end times.
+#[derive(Debug, Clone)]
+pub struct ExportRequest {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    /// Heading of the export, defaults to "Hours Overview" and the date range
+    pub title: Option<String>,
+    pub format: ExportFormat,
+    pub duration_format: DurationFormat,
+    pub columns: Vec<ExportColumn>,
+    pub group_by: Option<GroupBy>,
+    pub include_time_tracked: bool,
+    pub include_time_rounded: bool,
+    pub include_amount_rounded: bool,
+    /// Leaves out the entries and only shows the sums of each group
+    pub summary_only: bool,
+}
+
+impl ExportRequest {
+    pub fn new(from: NaiveDate, to: NaiveDate) -> Self {
+        Self {
+            from,
+            to,
+            title: None,
+            format: ExportFormat::default(),
+            duration_format: DurationFormat::default(),
+            columns: ExportColumn::ALL
+                .into_iter()
+                .filter(|column| *column != ExportColumn::StartEnd)
+                .collect(),
+            group_by: Some(GroupBy::Date),
+            include_time_tracked: true,
+            include_time_rounded: false,
+            include_amount_rounded: false,
+            summary_only: false,
+        }
+    }
+
+    /// Exports all entries of the given month, returns `None` if there is no such month.
+    pub fn month(year: i32, month: u32) -> Option<Self> {
+        let (from, to) = month_bounds(year, month)?;
+        Some(Self::new(from, to))
+    }
+
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    pub fn format(mut self, format: ExportFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    pub fn duration_format(mut self, duration_format: DurationFormat) -> Self {
+        self.duration_format = duration_format;
+        self
+    }
+
+    /// Shows exactly `columns`, in the order CoffeeCup puts them.
+    pub fn columns(mut self, columns: &[ExportColumn]) -> Self {
+        self.columns = columns.to_vec();
+        self
+    }
+
+    pub fn group_by(mut self, group_by: Option<GroupBy>) -> Self {
+        self.group_by = group_by;
+        self
+    }
+
+    pub fn include_time_rounded(mut self, include: bool) -> Self {
+        self.include_time_rounded = include;
+        self
+    }
+
+    pub fn include_amount_rounded(mut self, include: bool) -> Self {
+        self.include_amount_rounded = include;
+        self
+    }
+
+    pub fn summary_only(mut self, summary_only: bool) -> Self {
+        self.summary_only = summary_only;
+        self
+    }
+
+    /// Renders the request as the query parameters of `/v1/export/timeEntries`.
+    pub fn query(&self) -> Vec<(String, String)> {
+        let title = self.title.clone().unwrap_or_else(|| {
+            format!(
+                "Hours Overview {} - {}",
+                self.from.format("%d.%m.%Y"),
+                self.to.format("%d.%m.%Y")
+            )
+        });
+        let mut query = vec![
+            (
+                "start_date".to_string(),
+                format!("{}T00:00:00.000Z", self.from),
+            ),
+            ("end_date".to_string(), format!("{}T23:59:59.999Z", self.to)),
+            ("title".to_string(), title),
+            (
+                "duration_format".to_string(),
+                self.duration_format.code().to_string(),
+            ),
+            (
+                "include_time_tracked".to_string(),
+                self.include_time_tracked.to_string(),
+            ),
+            (
+                "include_time_rounded".to_string(),
+                self.include_time_rounded.to_string(),
+            ),
+            (
+                "include_amount_rounded".to_string(),
+                self.include_amount_rounded.to_string(),
+            ),
+        ];
+        for column in ExportColumn::ALL {
+            query.push((
+                format!("columns[{column}]"),
+                self.columns.contains(&column).to_string(),
+            ));
+        }
+        if let Some(group_by) = self.group_by {
+            query.push(("group_by".to_string(), group_by.to_string()));
+        }
+        query.push((
+            "show_only_summary_headers".to_string(),
+            self.summary_only.to_string(),
+        ));
+        query.push(("format".to_string(), self.format.to_string()));
+        query
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::coffeecup::export::{ExportColumn, ExportFormat, ExportRequest, GroupBy};
+
+    fn get<'a>(query: &'a [(String, String)], key: &str) -> Option<&'a str> {
+        query
+            .iter()
+            .find(|(name, _)| name == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    #[test]
+    fn test_default_query() {
+        let query = ExportRequest::month(2025, 6).unwrap().query();
+
+        assert_eq!(get(&query, "start_date"), Some("2025-06-01T00:00:00.000Z"));
+        assert_eq!(get(&query, "end_date"), Some("2025-06-30T23:59:59.999Z"));
+        assert_eq!(
+            get(&query, "title"),
+            Some("Hours Overview 01.06.2025 - 30.06.2025")
+        );
+        assert_eq!(get(&query, "duration_format"), Some("0"));
+        assert_eq!(get(&query, "columns[client]"), Some("true"));
+        assert_eq!(get(&query, "columns[start_end]"), Some("false"));
+        assert_eq!(get(&query, "group_by"), Some("date"));
+        assert_eq!(get(&query, "format"), Some("pdf"));
+    }
+
+    #[test]
+    fn test_customized_query() {
+        let query = ExportRequest::month(2025, 2)
+            .unwrap()
+            .title("Stunden Februar")
+            .format(ExportFormat::Xlsx)
+            .columns(&[ExportColumn::Date, ExportColumn::User])
+            .group_by(Some(GroupBy::User))
+            .summary_only(true)
+            .query();
+
+        assert_eq!(get(&query, "end_date"), Some("2025-02-28T23:59:59.999Z"));
+        assert_eq!(get(&query, "title"), Some("Stunden Februar"));
+        assert_eq!(get(&query, "columns[user]"), Some("true"));
+        assert_eq!(get(&query, "columns[project]"), Some("false"));
+        assert_eq!(get(&query, "group_by"), Some("user"));
+        assert_eq!(get(&query, "show_only_summary_headers"), Some("true"));
+        assert_eq!(get(&query, "format"), Some("xlsx"));
+        assert!(ExportRequest::month(2025, 13).is_none());
+    }
+
+    #[test]
+    fn test_parse_format() {
+        assert_eq!("csv".parse::<ExportFormat>().unwrap(), ExportFormat::Csv);
+        assert!("docx".parse::<ExportFormat>().is_err());
+    }
+}
@@ -0,0 +1,123 @@
+//! Helpers for running the CoffeeCup client against a local mock server in tests.
+
+use crate::bdwh::structs::TimeEntry as BdwhTimeEntry;
+use crate::coffeecup::auth::Credentials;
+use crate::coffeecup::client::{CoffeeCup, ListResponse, ProvidesPayloadFieldName};
+use crate::coffeecup::config::CoffeeCupConfig;
+use crate::coffeecup::resolver::Resolver;
+use chrono::{NaiveDate, NaiveTime};
+use serde::de::DeserializeOwned;
+use std::fs::read_to_string;
+use wiremock::matchers::{header, method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+pub(crate) fn fixture(name: &str) -> String {
+    read_to_string(format!("testdata/coffeecup/{name}"))
+        .expect("Failed to read test data from file!")
+}
+
+pub(crate) fn json_response(name: &str) -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_raw(fixture(name), "application/json")
+}
+
+/// Answers a create or update request with the entry in `get_timeentry.json`.
+pub(crate) fn timeentry_response(status: u16) -> ResponseTemplate {
+    ResponseTemplate::new(status).set_body_raw(fixture("get_timeentry.json"), "application/json")
+}
+
+/// Starts a mock server that hands out tokens like a CoffeeCup tenant would.
+pub(crate) async fn mock_server() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/oauth2/token"))
+        .respond_with(json_response("token.json"))
+        .mount(&server)
+        .await;
+    server
+}
+
+pub(crate) async fn mock_list(server: &MockServer, endpoint: &str, fixture_name: &str) {
+    Mock::given(method("GET"))
+        .and(path(format!("/v1/{endpoint}")))
+        .and(header(
+            "authorization",
+            "Bearer 3a8f1c0e2d4b6a7988e1f0c2d3b4a5968778695a",
+        ))
+        .respond_with(json_response(fixture_name))
+        .mount(server)
+        .await;
+}
+
+pub(crate) async fn client_for(server: &MockServer) -> CoffeeCup {
+    let config = CoffeeCupConfig::new(&format!("{}/v1", server.uri()), &server.uri()).unwrap();
+    CoffeeCup::new_with_config(
+        config,
+        Credentials::password("user@example.com", "secret"),
+        None,
+    )
+    .await
+    .unwrap()
+}
+
+/// Serves the three projects in the fixtures as two pages.
+pub(crate) async fn mock_projects(server: &MockServer) {
+    Mock::given(method("GET"))
+        .and(path("/v1/projects"))
+        .and(query_param("skip", "0"))
+        .respond_with(json_response("list_projects_page1.json"))
+        .mount(server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/v1/projects"))
+        .and(query_param("skip", "2"))
+        .respond_with(json_response("list_projects_page2.json"))
+        .mount(server)
+        .await;
+}
+
+/// Serves everything [`Resolver::load`] needs.
+pub(crate) async fn mock_mappings(server: &MockServer) {
+    mock_projects(server).await;
+    mock_list(server, "tasks", "list_tasks.json").await;
+    mock_list(server, "taskassignments", "list_taskassignments.json").await;
+    mock_list(server, "users", "list_users.json").await;
+    mock_list(server, "teams", "list_teams.json").await;
+}
+
+pub(crate) fn parse_list<T>(name: &str) -> Vec<T>
+where
+    T: ProvidesPayloadFieldName + DeserializeOwned,
+{
+    serde_json::from_str::<ListResponse<T>>(&fixture(name))
+        .expect("Failed to parse test data!")
+        .payload
+}
+
+/// A resolver knowing the projects, tasks, users and teams in the fixtures.
+pub(crate) fn resolver() -> Resolver {
+    let mut projects = parse_list("list_projects_page1.json");
+    projects.extend(parse_list("list_projects_page2.json"));
+    Resolver::new(
+        &projects,
+        &parse_list("list_tasks.json"),
+        &parse_list("list_taskassignments.json"),
+        &parse_list("list_users.json"),
+        &parse_list("list_teams.json"),
+    )
+}
+
+pub(crate) fn bdwh_entry() -> BdwhTimeEntry {
+    BdwhTimeEntry {
+        date: NaiveDate::from_ymd_opt(2025, 6, 2).unwrap(),
+        start_time: NaiveTime::from_hms_opt(9, 0, 0),
+        end_time: NaiveTime::from_hms_opt(9, 30, 0),
+        total_time: 0.5,
+        billable_time: 0.5,
+        cc_project: "10".to_string(),
+        ban: "001105".to_string(),
+        cc_id: "42".to_string(),
+        cc_team: "3".to_string(),
+        cc_task: "210".to_string(),
+        comment: "Kafka mit Kerberos und Active Directory getestet".to_string(),
+    }
+}
@@ -1,2 +1,10 @@
+pub mod auth;
+pub mod catalog;
 pub mod client;
+pub mod config;
+pub mod conversion;
+pub mod export;
+#[cfg(test)]
+pub(crate) mod mock;
+pub mod resolver;
 pub mod  structs;
@@ -0,0 +1,418 @@
+use crate::bdwh::structs::TimeEntry as BdwhTimeEntry;
+use crate::coffeecup::client::{self, CoffeeCup};
+use crate::coffeecup::conversion::{self, ConversionPolicy};
+use crate::coffeecup::structs::{
+    Project, Task, TaskAssignment, Team, TeamMembership, TimeEntry, User,
+};
+use snafu::{ResultExt, Snafu};
+use std::collections::{HashMap, HashSet};
+
+#[derive(Snafu, Debug)]
+pub enum Error {
+    #[snafu(display("Found no CoffeeCup {kind} matching [{value}] for {row}"))]
+    Unknown {
+        kind: String,
+        value: String,
+        row: String,
+    },
+    #[snafu(display(
+        "Found several CoffeeCup {kind}s matching [{value}] for {row}: {candidates:?}"
+    ))]
+    Ambiguous {
+        kind: String,
+        value: String,
+        candidates: Vec<usize>,
+        row: String,
+    },
+    #[snafu(display("CoffeeCup task {task} is not assigned to project {project} for {row}"))]
+    TaskNotAssigned {
+        task: usize,
+        project: usize,
+        row: String,
+    },
+    #[snafu(display("CoffeeCup user {user} is not a member of team {team} for {row}"))]
+    NotTeamMember {
+        user: usize,
+        team: usize,
+        row: String,
+    },
+    #[snafu(display("Failed to convert {row}: {source}"))]
+    Convert {
+        source: conversion::Error,
+        row: String,
+    },
+}
+
+/// CoffeeCup IDs a BDWH row refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResolvedIds {
+    pub project: usize,
+    pub task: usize,
+    pub user: usize,
+    pub team: usize,
+}
+
+/// Maps the CoffeeCup references in BDWH rows to IDs.
+///
+/// The person and BAN mappings in BDWH may refer to CoffeeCup objects by ID or by a human
+/// readable key, which is matched case-insensitively:
+///
+/// * projects by code or name
+/// * tasks by label
+/// * users by email or "firstname lastname"
+/// * teams by name
+pub struct Resolver {
+    projects: Index,
+    tasks: Index,
+    users: Index,
+    teams: Index,
+    /// `(project, task)` pairs that can be booked on
+    assignments: HashSet<(usize, usize)>,
+}
+
+impl Resolver {
+    /// Loads everything needed for resolving from CoffeeCup.
+    pub async fn load(coffeecup: &CoffeeCup) -> Result<Self, client::Error> {
+        let (projects, tasks, task_assignments, users, teams) = tokio::try_join!(
+            coffeecup.list_projects(),
+            coffeecup.list_tasks(),
+            coffeecup.list_task_assignments(),
+            coffeecup.list_users(),
+            coffeecup.list_teams(),
+        )?;
+
+        Ok(Self::new(
+            &projects,
+            &tasks,
+            &task_assignments,
+            &users,
+            &teams,
+        ))
+    }
+
+    pub fn new(
+        projects: &[Project],
+        tasks: &[Task],
+        task_assignments: &[TaskAssignment],
+        users: &[User],
+        teams: &[Team],
+    ) -> Self {
+        let mut resolver = Self {
+            projects: Index::default(),
+            tasks: Index::default(),
+            users: Index::default(),
+            teams: Index::default(),
+            assignments: task_assignments
+                .iter()
+                .map(|assignment| (assignment.project, assignment.task))
+                .collect(),
+        };
+        for project in projects {
+            let keys = [project.code.as_deref(), Some(project.name.as_str())];
+            resolver
+                .projects
+                .insert(project.id, keys.into_iter().flatten());
+        }
+        for task in tasks {
+            resolver.tasks.insert(task.id, [task.label.as_str()]);
+        }
+        for user in users {
+            let full_name = format!("{} {}", user.firstname, user.lastname);
+            resolver
+                .users
+                .insert(user.id, [user.email.as_str(), full_name.as_str()]);
+        }
+        for team in teams {
+            resolver.teams.insert(team.id, [team.name.as_str()]);
+        }
+        resolver
+    }
+
+    /// Returns the CoffeeCup IDs `entry` refers to.
+    pub fn resolve(&self, entry: &BdwhTimeEntry) -> Result<ResolvedIds, Error> {
+        let row = describe(entry);
+        let project = find(&self.projects, "project", &entry.cc_project, &row)?;
+        let task = find(&self.tasks, "task", &entry.cc_task, &row)?;
+        let user = find(&self.users, "user", &entry.cc_id, &row)?;
+        let team = find(&self.teams, "team", &entry.cc_team, &row)?;
+
+        if !self.assignments.contains(&(project, task)) {
+            return TaskNotAssignedSnafu { task, project, row }.fail();
+        }
+
+        Ok(ResolvedIds {
+            project,
+            task,
+            user,
+            team,
+        })
+    }
+
+    /// Checks that the person with the code `code` maps to an existing CoffeeCup user by
+    /// `cc_id`, who is a member of the team `cc_team` maps to according to `memberships`.
+    pub fn check_person(
+        &self,
+        code: &str,
+        cc_id: &str,
+        cc_team: &str,
+        memberships: &[TeamMembership],
+    ) -> Result<(), Error> {
+        let row = format!("person [{code}]");
+        let user = find(&self.users, "user", cc_id, &row)?;
+        let team = find(&self.teams, "team", cc_team, &row)?;
+
+        let is_member = memberships
+            .iter()
+            .any(|membership| membership.user == user && membership.team == team);
+        if !is_member {
+            return NotTeamMemberSnafu { user, team, row }.fail();
+        }
+        Ok(())
+    }
+
+    /// Converts `entry` into the entries that are created in CoffeeCup for it.
+    pub fn to_timeentries(
+        &self,
+        entry: BdwhTimeEntry,
+        policy: &ConversionPolicy,
+    ) -> Result<Vec<TimeEntry>, Error> {
+        let ids = self.resolve(&entry)?;
+        let row = describe(&entry);
+        TimeEntry::from_bdwh(entry, &ids, policy).context(ConvertSnafu { row })
+    }
+}
+
+/// Finds the IDs of CoffeeCup objects of one kind by ID or by one of their keys.
+#[derive(Default)]
+struct Index {
+    ids: HashSet<usize>,
+    keys: HashMap<String, Vec<usize>>,
+}
+
+impl Index {
+    fn insert<'a>(&mut self, id: usize, keys: impl IntoIterator<Item = &'a str>) {
+        self.ids.insert(id);
+        for key in keys {
+            let ids = self.keys.entry(normalize(key)).or_default();
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+    }
+
+    fn get(&self, value: &str) -> &[usize] {
+        self.keys
+            .get(&normalize(value))
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+}
+
+/// Looks up `value` as ID and as key.
+///
+/// Values like the BAN `001105` parse as the ID 1105, so a value matching both an ID and the
+/// key of another object is ambiguous.
+fn find(index: &Index, kind: &str, value: &str, row: &str) -> Result<usize, Error> {
+    let by_id = value
+        .trim()
+        .parse::<usize>()
+        .ok()
+        .filter(|id| index.ids.contains(id));
+    let mut candidates: Vec<usize> = by_id
+        .into_iter()
+        .chain(index.get(value).iter().copied())
+        .collect();
+    candidates.sort_unstable();
+    candidates.dedup();
+    match candidates.as_slice() {
+        [id] => Ok(*id),
+        [] => UnknownSnafu { kind, value, row }.fail(),
+        candidates => AmbiguousSnafu {
+            kind,
+            value,
+            candidates: candidates.to_vec(),
+            row,
+        }
+        .fail(),
+    }
+}
+
+/// Turns a name, code or label into the form it is matched in, ignoring case and surrounding
+/// whitespace.
+pub(crate) fn normalize(key: &str) -> String {
+    key.trim().to_lowercase()
+}
+
+/// Identifies a BDWH row for people reading an error message.
+fn describe(entry: &BdwhTimeEntry) -> String {
+    let times = match (entry.start_time, entry.end_time) {
+        (Some(start_time), Some(end_time)) => format!(" {start_time}-{end_time}"),
+        _ => String::new(),
+    };
+    format!(
+        "the entry of [{}] on {}{} booked on BAN [{}]",
+        entry.cc_id, entry.date, times, entry.ban
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::coffeecup::mock::{
+        bdwh_entry, client_for, mock_mappings, mock_server, parse_list, resolver,
+    };
+    use crate::coffeecup::resolver::{Error, ResolvedIds, Resolver};
+    use crate::coffeecup::structs::{Project, TeamMembership};
+
+    #[tokio::test]
+    async fn test_load_from_coffeecup() {
+        let server = mock_server().await;
+        mock_mappings(&server).await;
+        let cc_client = client_for(&server).await;
+
+        let resolver = Resolver::load(&cc_client).await.unwrap();
+
+        let mut entry = bdwh_entry();
+        entry.cc_project = "Internal Tooling".to_string();
+        entry.cc_task = "Development".to_string();
+        entry.cc_id = "john.roe@stackable.tech".to_string();
+        let ids = resolver.resolve(&entry).unwrap();
+        assert_eq!((ids.project, ids.task, ids.user), (12, 212, 43));
+    }
+
+    #[test]
+    fn test_resolve_ids() {
+        let ids = resolver().resolve(&bdwh_entry()).unwrap();
+
+        assert_eq!(
+            ids,
+            ResolvedIds {
+                project: 10,
+                task: 210,
+                user: 42,
+                team: 3
+            }
+        );
+    }
+
+    #[test]
+    fn test_resolve_names() {
+        let mut entry = bdwh_entry();
+        entry.cc_project = "001105".to_string();
+        entry.cc_task = "consulting".to_string();
+        entry.cc_id = "Jane.Doe@stackable.tech".to_string();
+        entry.cc_team = "Engineering".to_string();
+
+        let ids = resolver().resolve(&entry).unwrap();
+
+        assert_eq!(ids.project, 10);
+        assert_eq!(ids.task, 210);
+        assert_eq!(ids.user, 42);
+        assert_eq!(ids.team, 3);
+
+        entry.cc_project = "Kafka Kerberos Integration".to_string();
+        entry.cc_id = "Jane Doe".to_string();
+        assert_eq!(resolver().resolve(&entry).unwrap().user, 42);
+    }
+
+    #[test]
+    fn test_resolve_unknown_user() {
+        let mut entry = bdwh_entry();
+        entry.cc_id = "max.mustermann@stackable.tech".to_string();
+
+        let error = resolver().resolve(&entry).unwrap_err();
+
+        assert!(matches!(&error, Error::Unknown { kind, .. } if kind == "user"));
+        assert_eq!(
+            error.to_string(),
+            "Found no CoffeeCup user matching [max.mustermann@stackable.tech] for the entry of \
+            [max.mustermann@stackable.tech] on 2025-06-02 09:00:00-09:30:00 booked on BAN [001105]"
+        );
+    }
+
+    #[test]
+    fn test_resolve_unknown_id() {
+        let mut entry = bdwh_entry();
+        entry.cc_project = "99".to_string();
+
+        let error = resolver().resolve(&entry).unwrap_err();
+
+        assert!(matches!(error, Error::Unknown { kind, .. } if kind == "project"));
+    }
+
+    #[test]
+    fn test_resolve_id_matching_another_code() {
+        let mut projects: Vec<Project> = parse_list("list_projects_page1.json");
+        let mut other = projects[1].clone();
+        other.id = 1105;
+        other.code = Some("009999".to_string());
+        other.name = "Other".to_string();
+        projects.push(other);
+        let resolver = Resolver::new(
+            &projects,
+            &parse_list("list_tasks.json"),
+            &parse_list("list_taskassignments.json"),
+            &parse_list("list_users.json"),
+            &parse_list("list_teams.json"),
+        );
+        let mut entry = bdwh_entry();
+        entry.cc_project = "001105".to_string();
+
+        let error = resolver.resolve(&entry).unwrap_err();
+
+        assert!(matches!(
+            error,
+            Error::Ambiguous { kind, candidates, .. } if kind == "project" && candidates == [10, 1105]
+        ));
+        // Values that only match an ID are still IDs
+        entry.cc_project = "1105".to_string();
+        entry.cc_task = "Support".to_string();
+        assert!(matches!(
+            resolver.resolve(&entry),
+            Err(Error::TaskNotAssigned { project: 1105, .. })
+        ));
+    }
+
+    #[test]
+    fn test_resolve_task_not_assigned() {
+        let mut entry = bdwh_entry();
+        // Development is only assigned to project 12
+        entry.cc_task = "Development".to_string();
+
+        let error = resolver().resolve(&entry).unwrap_err();
+
+        assert!(matches!(
+            error,
+            Error::TaskNotAssigned {
+                task: 212,
+                project: 10,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_check_person() {
+        let memberships: Vec<TeamMembership> = parse_list("list_teammemberships.json");
+        assert!(resolver()
+            .check_person(
+                "SIWE",
+                "jane.doe@stackable.tech",
+                "Engineering",
+                &memberships
+            )
+            .is_ok());
+
+        let error = resolver()
+            .check_person("SIWE", "43", "3", &memberships)
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "CoffeeCup user 43 is not a member of team 3 for person [SIWE]"
+        );
+
+        let error = resolver()
+            .check_person("SIWE", "Max Mustermann", "3", &memberships)
+            .unwrap_err();
+        assert!(matches!(error, Error::Unknown { kind, .. } if kind == "user"));
+    }
+}
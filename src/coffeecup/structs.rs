@@ -1,9 +1,16 @@
 use crate::bdwh::structs::TimeEntry as BdwhTimeEntry;
-use chrono::{NaiveDate, NaiveTime};
-use serde::{Deserialize, Serialize};
+use crate::coffeecup::client::{Error, InvalidCustomFieldSnafu};
+use crate::coffeecup::conversion::{self, ConversionPolicy, Portion};
+use crate::coffeecup::resolver::ResolvedIds;
+use chrono::{Datelike, NaiveDate, NaiveTime, Weekday};
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::json;
+use snafu::ensure;
+use std::collections::BTreeMap;
 use strum_macros::{Display, EnumString};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Tag {
     pub id: usize,
     pub label: String,
@@ -14,39 +21,215 @@ pub struct Tag {
     pub category: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// The fields of a tag that are set when creating or updating it, unset fields are left out.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TagFields {
+    pub label: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub tag_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+}
+
+impl TagFields {
+    pub fn new(label: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+            ..Self::default()
+        }
+    }
+}
+
+/// Kinds of records tags can be assigned to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, EnumString, Display)]
+#[serde(rename_all = "camelCase")]
+#[strum(serialize_all = "camelCase")]
+pub enum TagModel {
+    Project,
+    Client,
+    User,
+    TimeEntry,
+    /// Any kind of record not listed here, only ever read from CoffeeCup
+    #[serde(other)]
+    Other,
+}
+
+/// Assigns the tag with the ID `tag` to the record with the ID `record`, which is of the kind
+/// named by `model`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TagAssignment {
+    pub id: usize,
+    pub record: usize,
+    pub model: TagModel,
+    pub tag: usize,
+}
+
+impl TagAssignment {
+    /// Returns true if the assignment is on the record of kind `model` with the ID `record`.
+    pub fn is_on(&self, model: TagModel, record: usize) -> bool {
+        self.record == record && self.model == model
+    }
+}
+
+/// Restricts which tag assignments are returned by
+/// [`crate::coffeecup::client::CoffeeCup::get_tag_assignments`].
+#[derive(Debug, Clone, Default)]
+pub struct TagAssignmentFilter {
+    pub tag: Option<usize>,
+    pub model: Option<TagModel>,
+    pub record: Option<usize>,
+}
+
+impl TagAssignmentFilter {
+    /// Assignments of the tag with the ID `tag` to records of kind `model`.
+    pub fn tag(tag: usize, model: TagModel) -> Self {
+        Self {
+            tag: Some(tag),
+            model: Some(model),
+            record: None,
+        }
+    }
+
+    /// Assignments to the record of kind `model` with the ID `record`.
+    pub fn record(model: TagModel, record: usize) -> Self {
+        Self {
+            tag: None,
+            model: Some(model),
+            record: Some(record),
+        }
+    }
+
+    pub fn where_clause(&self) -> serde_json::Value {
+        WhereClause::default()
+            .eq("tag", self.tag)
+            .eq("model", self.model)
+            .eq("record", self.record)
+            .into()
+    }
+}
+
+/// Body of requests and responses that carry a single time entry.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TimeEntryWrapper {
     pub timeEntry: TimeEntry,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// A time entry, as it is sent to and returned by the CoffeeCup API.
+///
+/// Fields that are maintained by CoffeeCup, such as the ID, are `None` for entries that have
+/// not been created yet and are left out when sending entries.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TimeEntry {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub createdAt: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub updatedAt: Option<String>,
     pub trackingType: TrackingType,
-    pub validationStatus: ValidationStatus,
-    pub day: NaiveDate,
     pub startTime: Option<NaiveTime>,
     pub endTime: Option<NaiveTime>,
+    pub day: NaiveDate,
+    pub sorting: Option<isize>,
     pub duration: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub durationRounded: Option<usize>,
+    pub durationRoundedOverride: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub estimate: Option<usize>,
     pub running: bool,
-    pub comment: String,
-    pub billedAt: Option<NaiveDate>,
+    pub comment: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hourlyRate: Option<f64>,
+    pub billedAt: Option<String>,
+    /// Left to CoffeeCup if unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub billable: Option<bool>,
+    pub validationStatus: ValidationStatus,
     pub wasRejected: bool,
     pub firstSubmissionTime: Option<String>,
-    pub overdueHours: Option<String>,
     pub approvedOn: Option<String>,
-    pub createdAt: Option<String>,
-    pub updatedAt: Option<String>,
-    pub validation: Option<String>,
-    pub project: String,
-    pub task: String,
-    pub user: String,
-    pub team: String,
-    pub timeEntryReference: Option<String>,
-    pub reference: Option<String>,
-    pub invoice: Option<String>,
-    pub approvedBy: Option<bool>,
-    pub sorting: Option<isize>,
-    pub durationRoundedOverride: Option<usize>,
+    pub externalId: Option<String>,
+    pub team: Option<usize>,
+    pub task: usize,
+    pub project: usize,
+    pub user: usize,
+    pub invoice: Option<usize>,
+    pub approvedBy: Option<usize>,
+}
+
+/// Restricts which time entries are returned by [`crate::coffeecup::client::CoffeeCup::get_timeentries`].
+///
+/// All criteria that are set are combined, unset criteria are not applied at all.
+#[derive(Debug, Clone, Default)]
+pub struct TimeEntryFilter {
+    pub team: Option<usize>,
+    pub projects: Option<Vec<usize>>,
+    pub user: Option<usize>,
+    /// First day to include (inclusive)
+    pub from: Option<NaiveDate>,
+    /// Last day to include (inclusive)
+    pub to: Option<NaiveDate>,
+    pub validation_status: Option<ValidationStatus>,
+    /// Entries that have been created with one of these `externalId`s
+    pub external_ids: Option<Vec<String>>,
+}
+
+impl TimeEntryFilter {
+    /// Renders the filter as the JSON `where` clause understood by the CoffeeCup API.
+    pub fn where_clause(&self) -> serde_json::Value {
+        WhereClause::default()
+            .eq("team", self.team)
+            .one_of("project", self.projects.as_deref())
+            .eq("user", self.user)
+            .between("day", self.from, self.to)
+            .eq("validationStatus", self.validation_status.as_ref())
+            .one_of("externalId", self.external_ids.as_deref())
+            .into()
+    }
+}
+
+/// Builds the JSON `where` clause of list requests, leaving out conditions on unset values.
+#[derive(Default)]
+struct WhereClause(serde_json::Map<String, serde_json::Value>);
+
+impl WhereClause {
+    fn eq(mut self, field: &str, value: Option<impl Serialize>) -> Self {
+        if let Some(value) = value {
+            self.0.insert(field.to_string(), json!(value));
+        }
+        self
+    }
+
+    fn one_of(mut self, field: &str, values: Option<&[impl Serialize]>) -> Self {
+        if let Some(values) = values {
+            self.0.insert(field.to_string(), json!({ "in": values }));
+        }
+        self
+    }
+
+    /// Both bounds are inclusive.
+    fn between(mut self, field: &str, from: Option<NaiveDate>, to: Option<NaiveDate>) -> Self {
+        let mut range = serde_json::Map::new();
+        if let Some(from) = from {
+            range.insert(">=".to_string(), json!(from));
+        }
+        if let Some(to) = to {
+            range.insert("<=".to_string(), json!(to));
+        }
+        if !range.is_empty() {
+            self.0.insert(field.to_string(), serde_json::Value::Object(range));
+        }
+        self
+    }
+}
+
+impl From<WhereClause> for serde_json::Value {
+    fn from(clause: WhereClause) -> Self {
+        serde_json::Value::Object(clause.0)
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -89,9 +272,146 @@ pub struct Project {
     pub tasks: Option<Vec<Task>>,
 }
 
+/// Who the hours of a project are billed by, the `billBy` of a project.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BillBy {
+    /// Hours are not billed
+    NotBillable,
+    /// At the hourly rate of the project
+    Project,
+    /// At the hourly rates of its tasks
+    Task,
+    /// At the hourly rates of the users tracking time
+    User,
+}
+
+impl BillBy {
+    /// Returns the number CoffeeCup expects for the billing mode.
+    pub fn code(self) -> usize {
+        match self {
+            Self::NotBillable => 0,
+            Self::Project => 1,
+            Self::Task => 2,
+            Self::User => 3,
+        }
+    }
+}
+
+impl Serialize for BillBy {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.code().serialize(serializer)
+    }
+}
+
+/// Budget of a project, the `budgetBy` of a project together with the amount.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Budget {
+    /// Number of hours that can be booked in total
+    Hours(f64),
+    /// Amount of money that can be billed in total
+    Fees(f64),
+}
+
+impl Serialize for Budget {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let (budget_by, field, amount) = match self {
+            Self::Hours(hours) => (1, "budgetHours", hours),
+            Self::Fees(fees) => (2, "budget", fees),
+        };
+        let mut map = serializer.serialize_map(Some(2))?;
+        map.serialize_entry("budgetBy", &budget_by)?;
+        map.serialize_entry(field, amount)?;
+        map.end()
+    }
+}
+
+/// Describes a project to create with [`crate::coffeecup::client::CoffeeCup::create_project`].
+///
+/// Only the name is required, everything else is left out of the request and to the defaults
+/// of CoffeeCup unless set.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewProject {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    /// ID of the client the project is done for
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bill_by: Option<BillBy>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hourly_rate: Option<f64>,
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    pub budget: Option<Budget>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_date: Option<NaiveDate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_date: Option<NaiveDate>,
+    /// Values by field name, `customField1` to `customField10`
+    #[serde(flatten)]
+    custom_fields: BTreeMap<String, String>,
+}
+
+impl NewProject {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ..Self::default()
+        }
+    }
+
+    pub fn code(mut self, code: impl Into<String>) -> Self {
+        self.code = Some(code.into());
+        self
+    }
+
+    pub fn client(mut self, client: usize) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    pub fn comment(mut self, comment: impl Into<String>) -> Self {
+        self.comment = Some(comment.into());
+        self
+    }
+
+    /// Bills hours by `bill_by`, at `hourly_rate` if they are billed by project.
+    pub fn billing(mut self, bill_by: BillBy, hourly_rate: Option<f64>) -> Self {
+        self.bill_by = Some(bill_by);
+        self.hourly_rate = hourly_rate;
+        self
+    }
+
+    pub fn budget(mut self, budget: Budget) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+
+    pub fn dates(mut self, start_date: NaiveDate, end_date: Option<NaiveDate>) -> Self {
+        self.start_date = Some(start_date);
+        self.end_date = end_date;
+        self
+    }
+
+    /// Sets `customField{number}`, fails unless `number` is one of the custom fields 1 to 10
+    /// CoffeeCup has.
+    pub fn custom_field(mut self, number: u8, value: impl Into<String>) -> Result<Self, Error> {
+        ensure!(
+            (1..=10).contains(&number),
+            InvalidCustomFieldSnafu { number }
+        );
+        self.custom_fields
+            .insert(format!("customField{number}"), value.into());
+        Ok(self)
+    }
+}
 
 
-#[derive(Debug, Serialize, Deserialize)]
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Customer {
     pub id: usize,
     pub status: usize,
@@ -99,7 +419,7 @@ pub struct Customer {
 }
 
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TaskAssignment {
     pub task: usize,
     pub project: usize,
@@ -112,56 +432,209 @@ pub struct Task {
     pub label: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct User {
+    pub id: usize,
+    pub firstname: String,
+    pub lastname: String,
+    pub email: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Team {
+    pub id: usize,
+    pub name: String,
+}
+
+/// A kind of absence, such as vacation, sick leave or a public holiday.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AbsenceType {
+    pub id: usize,
+    pub name: String,
+    pub color: Option<String>,
+    /// Set for absences that are taken from the vacation allowance
+    #[serde(default)]
+    pub isVacation: bool,
+}
+
+/// A user being absent from `startDate` until `endDate`, both inclusive.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Absence {
+    pub id: usize,
+    pub user: usize,
+    pub absenceType: usize,
+    pub startDate: NaiveDate,
+    pub endDate: NaiveDate,
+    /// Set if the absence starts at noon
+    #[serde(default)]
+    pub startDateHalfDay: bool,
+    /// Set if the absence ends at noon
+    #[serde(default)]
+    pub endDateHalfDay: bool,
+    pub comment: Option<String>,
+}
+
+impl Absence {
+    /// Returns the share of `day` the user is absent, `0.0`, `0.5` or `1.0`.
+    pub fn share_of(&self, day: NaiveDate) -> f64 {
+        if day < self.startDate || day > self.endDate {
+            0.0
+        } else if (day == self.startDate && self.startDateHalfDay)
+            || (day == self.endDate && self.endDateHalfDay)
+        {
+            0.5
+        } else {
+            1.0
+        }
+    }
+}
+
+/// Restricts which absences are returned by [`crate::coffeecup::client::CoffeeCup::get_absences`].
+///
+/// Absences are returned if they overlap the range between `from` and `to`.
+#[derive(Debug, Clone, Default)]
+pub struct AbsenceFilter {
+    pub user: Option<usize>,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+impl AbsenceFilter {
+    pub fn where_clause(&self) -> serde_json::Value {
+        // Absences ending before the range or starting after it don't overlap
+        WhereClause::default()
+            .eq("user", self.user)
+            .between("endDate", self.from, None)
+            .between("startDate", None, self.to)
+            .into()
+    }
+}
+
+/// How long a user is employed to work on each day of the week, starting at `validFrom`.
+///
+/// A user has a new employment whenever their working time changes, the one with the latest
+/// `validFrom` before a day applies to it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserEmployment {
+    pub id: usize,
+    pub user: usize,
+    pub validFrom: NaiveDate,
+    /// Working time in seconds
+    #[serde(default)]
+    pub monday: usize,
+    #[serde(default)]
+    pub tuesday: usize,
+    #[serde(default)]
+    pub wednesday: usize,
+    #[serde(default)]
+    pub thursday: usize,
+    #[serde(default)]
+    pub friday: usize,
+    #[serde(default)]
+    pub saturday: usize,
+    #[serde(default)]
+    pub sunday: usize,
+    /// Vacation days per year
+    pub vacationDays: Option<f64>,
+}
+
+impl UserEmployment {
+    /// Returns the employment of `user` that applies to `day`, if they were employed then.
+    pub fn applicable(employments: &[Self], user: usize, day: NaiveDate) -> Option<&Self> {
+        employments
+            .iter()
+            .filter(|employment| employment.user == user && employment.validFrom <= day)
+            .max_by_key(|employment| employment.validFrom)
+    }
+
+    /// Returns the working time on `day` in seconds.
+    pub fn working_time(&self, day: NaiveDate) -> usize {
+        match day.weekday() {
+            Weekday::Mon => self.monday,
+            Weekday::Tue => self.tuesday,
+            Weekday::Wed => self.wednesday,
+            Weekday::Thu => self.thursday,
+            Weekday::Fri => self.friday,
+            Weekday::Sat => self.saturday,
+            Weekday::Sun => self.sunday,
+        }
+    }
+}
+
+/// Makes a user a member of a team.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TeamMembership {
+    pub id: usize,
+    pub team: usize,
+    pub user: usize,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, EnumString, Display)]
 pub enum TrackingType {
     WORK,
+    /// Any type this client doesn't know about yet
+    #[serde(other)]
+    Other,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, EnumString, Display)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, EnumString, Display)]
 pub enum ValidationStatus {
     NOTSUBMITTED,
+    SUBMITTED,
     APPROVED,
+    REJECTED,
 }
 
-impl From<BdwhTimeEntry> for TimeEntryWrapper {
-    fn from(value: BdwhTimeEntry) -> Self {
-        Self {
-            timeEntry: value.into(),
-        }
+impl TimeEntry {
+    /// Converts a BDWH row, whose CoffeeCup references have been resolved to `ids` by a
+    /// [`crate::coffeecup::resolver::Resolver`], into the entries `policy` splits it into.
+    pub fn from_bdwh(
+        value: BdwhTimeEntry,
+        ids: &ResolvedIds,
+        policy: &ConversionPolicy,
+    ) -> Result<Vec<Self>, conversion::Error> {
+        Ok(policy
+            .portions(&value)?
+            .into_iter()
+            .map(|portion| Self::from_portion(&value, ids, policy, portion))
+            .collect())
     }
-}
 
-impl From<BdwhTimeEntry> for TimeEntry {
-    fn from(value: BdwhTimeEntry) -> Self {
-        let duration = value.total_time * 3600.0;
-        let duration = duration.floor() as usize;
+    fn from_portion(
+        value: &BdwhTimeEntry,
+        ids: &ResolvedIds,
+        policy: &ConversionPolicy,
+        portion: Portion,
+    ) -> Self {
         Self {
+            id: None,
+            createdAt: None,
+            updatedAt: None,
             trackingType: TrackingType::WORK,
-            validationStatus: ValidationStatus::APPROVED,
+            startTime: portion.start_time,
+            endTime: portion.end_time,
             day: value.date,
-            startTime: None,
-            endTime: None,
-            duration,
+            sorting: Some(1),
+            duration: portion.duration,
+            durationRounded: None,
+            durationRoundedOverride: None,
+            estimate: None,
             running: false,
-            comment: value.comment,
+            comment: Some(value.comment.clone()),
+            hourlyRate: None,
             billedAt: None,
+            billable: portion.billable,
+            validationStatus: policy.validation_status.clone(),
             wasRejected: false,
             firstSubmissionTime: None,
-            overdueHours: None,
             approvedOn: None,
-            createdAt: None,
-            updatedAt: None,
-            validation: None,
-            project: value.cc_project,
-            task: value.cc_task,
-            user: value.cc_id,
-            team: value.cc_team,
-            timeEntryReference: None,
-            reference: None,
+            externalId: Some(portion.external_id),
+            team: Some(ids.team),
+            task: ids.task,
+            project: ids.project,
+            user: ids.user,
             invoice: None,
             approvedBy: None,
-            sorting: Some(1),
-            durationRoundedOverride: None,
         }
     }
 }
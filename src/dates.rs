@@ -0,0 +1,25 @@
+//! Calendar helpers shared by the BDWH and CoffeeCup sides.
+
+use chrono::{Months, NaiveDate};
+
+/// Returns the first and last day of the given month, `None` if there is no such month.
+pub fn month_bounds(year: i32, month: u32) -> Option<(NaiveDate, NaiveDate)> {
+    let first = NaiveDate::from_ymd_opt(year, month, 1)?;
+    let last = first.checked_add_months(Months::new(1))?.pred_opt()?;
+    Some((first, last))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dates::month_bounds;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_month_bounds() {
+        let day = |month, day| NaiveDate::from_ymd_opt(2024, month, day).unwrap();
+
+        assert_eq!(month_bounds(2024, 2), Some((day(2, 1), day(2, 29))));
+        assert_eq!(month_bounds(2024, 12), Some((day(12, 1), day(12, 31))));
+        assert_eq!(month_bounds(2024, 13), None);
+    }
+}
@@ -0,0 +1,207 @@
+use crate::easybill::structs::Document;
+use reqwest::header::AUTHORIZATION;
+use reqwest::{StatusCode, Url};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use snafu::{ResultExt, Snafu};
+
+/// Used unless another url is configured.
+pub const DEFAULT_URL: &str = "https://api.easybill.de/rest/v1/";
+
+/// The largest page size easybill accepts.
+const LIST_PAGE_SIZE: usize = 1000;
+
+#[derive(Snafu, Debug)]
+#[snafu(visibility(pub(crate)))]
+pub enum Error {
+    #[snafu(display("Failed to parse easybill url [{url}]: {source}"))]
+    ParseUrl {
+        source: oauth2::url::ParseError,
+        url: String,
+    },
+    #[snafu(display("Failed to {msg}:  {source}"))]
+    Reqwest { source: reqwest::Error, msg: String },
+    #[snafu(display("Failed to {msg} at [{url}]: {source}"))]
+    ReqwestWithUrl {
+        source: reqwest::Error,
+        msg: String,
+        url: String,
+    },
+    #[snafu(display("Failed to {msg}, [{url}] responded with {status}: {body}"))]
+    Status {
+        msg: String,
+        url: String,
+        status: StatusCode,
+        body: String,
+    },
+    #[snafu(display("Failed to parse response from [{url}]: {source} - response was: {body}"))]
+    DecodeJson {
+        source: serde_json::Error,
+        url: String,
+        body: String,
+    },
+}
+
+/// One page of an easybill list endpoint.
+#[derive(Debug, Deserialize)]
+struct Page<T> {
+    page: usize,
+    pages: usize,
+    items: Vec<T>,
+}
+
+/// Reads documents from the easybill REST API.
+pub struct Easybill {
+    url: Url,
+    api_key: String,
+    client: reqwest::Client,
+}
+
+impl Easybill {
+    /// Connects to the API at `url`, usually [`DEFAULT_URL`], with the API key of a login.
+    pub fn new(url: &str, api_key: &str) -> Result<Self, Error> {
+        // Url::join would drop the last path segment of a url without a trailing slash
+        let url = if url.ends_with('/') {
+            url.to_string()
+        } else {
+            format!("{url}/")
+        };
+        let url = Url::parse(&url).context(ParseUrlSnafu { url: url.as_str() })?;
+        let client = reqwest::ClientBuilder::new()
+            // Following redirects opens the client up to SSRF vulnerabilities.
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .context(ReqwestSnafu {
+                msg: "build client",
+            })?;
+        Ok(Self {
+            url,
+            api_key: api_key.to_string(),
+            client,
+        })
+    }
+
+    /// Retrieves all offers, including drafts.
+    pub async fn list_offers(&self) -> Result<Vec<Document>, Error> {
+        self.list_documents("OFFER").await
+    }
+
+    /// Retrieves all documents of `doctype`, for example `OFFER` or `INVOICE`.
+    pub async fn list_documents(&self, doctype: &str) -> Result<Vec<Document>, Error> {
+        let url = self.url.join("documents").context(ParseUrlSnafu {
+            url: self.url.as_str(),
+        })?;
+        let mut documents = Vec::new();
+
+        for page in 1.. {
+            let request = self.client.get(url.as_ref()).query(&[
+                ("type", doctype.to_string()),
+                ("limit", LIST_PAGE_SIZE.to_string()),
+                ("page", page.to_string()),
+            ]);
+            let page: Page<Document> = self.send_json(request, "list documents", &url).await?;
+            documents.extend(page.items);
+            if page.page >= page.pages {
+                break;
+            }
+        }
+        Ok(documents)
+    }
+
+    async fn send_json<T>(
+        &self,
+        request: reqwest::RequestBuilder,
+        msg: &str,
+        url: &Url,
+    ) -> Result<T, Error>
+    where
+        T: DeserializeOwned,
+    {
+        let response = request
+            .header(AUTHORIZATION, format!("Bearer {}", self.api_key))
+            .send()
+            .await
+            .context(ReqwestWithUrlSnafu {
+                msg,
+                url: url.as_ref(),
+            })?;
+        let status = response.status();
+        let body = response.text().await.context(ReqwestWithUrlSnafu {
+            msg: format!("read response to {msg}"),
+            url: url.as_ref(),
+        })?;
+        if !status.is_success() {
+            return StatusSnafu {
+                msg,
+                url: url.as_ref(),
+                status,
+                body,
+            }
+            .fail();
+        }
+
+        serde_json::from_str(&body).context(DecodeJsonSnafu {
+            url: url.as_ref(),
+            body,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::easybill::client::{Easybill, Error};
+    use serde_json::json;
+    use std::fs::read_to_string;
+    use wiremock::matchers::{header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_list_offers() {
+        let server = MockServer::start().await;
+        let mut page1: serde_json::Value =
+            serde_json::from_str(&read_to_string("testdata/easybill/list_documents.json").unwrap())
+                .unwrap();
+        let last = page1["items"].as_array_mut().unwrap().pop().unwrap();
+        page1["pages"] = json!(2);
+        let page2 = json!({ "page": 2, "pages": 2, "limit": 1000, "total": 3, "items": [last] });
+        for (page, body) in [("1", page1), ("2", page2)] {
+            Mock::given(method("GET"))
+                .and(path("/rest/v1/documents"))
+                .and(header("Authorization", "Bearer api-key"))
+                .and(query_param("type", "OFFER"))
+                .and(query_param("page", page))
+                .respond_with(ResponseTemplate::new(200).set_body_json(body))
+                .expect(1)
+                .mount(&server)
+                .await;
+        }
+
+        let offers = Easybill::new(&format!("{}/rest/v1", server.uri()), "api-key")
+            .unwrap()
+            .list_offers()
+            .await
+            .unwrap();
+
+        let ids: Vec<usize> = offers.iter().map(|offer| offer.id).collect();
+        assert_eq!(ids, [123, 124, 3210386482]);
+        assert_eq!(offers[0].customer_snapshot.company_name, "Company A");
+        assert_eq!(offers[0].status.as_deref(), Some("ACCEPT"));
+    }
+
+    #[tokio::test]
+    async fn test_list_offers_unauthorized() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(401).set_body_string("Unauthorized"))
+            .mount(&server)
+            .await;
+
+        let error = Easybill::new(&server.uri(), "wrong-key")
+            .unwrap()
+            .list_offers()
+            .await
+            .unwrap_err();
+
+        assert!(matches!(error, Error::Status { status, .. } if status == 401));
+    }
+}
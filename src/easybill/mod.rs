@@ -1 +1,2 @@
+pub mod client;
 pub mod structs;
\ No newline at end of file
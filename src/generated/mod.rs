@@ -0,0 +1,3 @@
+// The generated code follows the names used in Baserow and isn't used everywhere.
+#[allow(dead_code, non_camel_case_types, unused_imports)]
+pub mod customersuccess;
@@ -0,0 +1,309 @@
+//! Reads time entries from CSV exports of the legacy time tracking, see
+//! `examples/project_times.csv`.
+//!
+//! The rows are turned into the same [`TimeEntry`] values that are read from BDWH, so they can
+//! be migrated without access to Trino.
+
+use crate::bdwh::structs::TimeEntry;
+use chrono::NaiveDate;
+use serde::Deserialize;
+use snafu::{OptionExt, ResultExt, Snafu};
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::Path;
+
+#[derive(Snafu, Debug)]
+pub enum Error {
+    #[snafu(display("Failed to open [{path}]: {source}"))]
+    OpenFile {
+        source: std::io::Error,
+        path: String,
+    },
+    #[snafu(display("Failed to read CSV: {source}"))]
+    ReadCsv { source: csv::Error },
+    #[snafu(display("Invalid {field} [{value}] in line {line}"))]
+    InvalidValue {
+        field: String,
+        value: String,
+        line: u64,
+    },
+    #[snafu(display("Unknown person code [{code}] in line {line}"))]
+    UnknownPerson { code: String, line: u64 },
+}
+
+/// How a person is known to CoffeeCup.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Person {
+    pub code: String,
+    pub cc_id: String,
+    pub cc_team: String,
+}
+
+/// Maps the person codes used in exports to CoffeeCup users and teams.
+///
+/// This is the same mapping BDWH reads from the `persons` sheet, and can be read from a CSV
+/// file with the columns `code`, `cc_id` and `cc_team`.
+#[derive(Debug, Clone, Default)]
+pub struct PersonTable {
+    persons: HashMap<String, Person>,
+}
+
+impl PersonTable {
+    pub fn new(persons: impl IntoIterator<Item = Person>) -> Self {
+        Self {
+            persons: persons
+                .into_iter()
+                .map(|person| (person.code.clone(), person))
+                .collect(),
+        }
+    }
+
+    pub fn from_csv(reader: impl Read) -> Result<Self, Error> {
+        let persons = csv::Reader::from_reader(reader)
+            .deserialize()
+            .collect::<Result<Vec<Person>, _>>()
+            .context(ReadCsvSnafu)?;
+        Ok(Self::new(persons))
+    }
+
+    pub fn from_csv_file(path: &Path) -> Result<Self, Error> {
+        Self::from_csv(open(path)?)
+    }
+
+    pub fn get(&self, code: &str) -> Option<&Person> {
+        self.persons.get(code)
+    }
+
+    /// Returns all persons, ordered by code.
+    pub fn persons(&self) -> Vec<&Person> {
+        let mut persons: Vec<&Person> = self.persons.values().collect();
+        persons.sort_by(|a, b| a.code.cmp(&b.code));
+        persons
+    }
+}
+
+/// The CoffeeCup project and task an export is booked on.
+///
+/// Exports are made per project, so this is the same for all of their rows.
+#[derive(Debug, Clone)]
+pub struct ProjectMapping {
+    pub ban: String,
+    pub cc_project: String,
+    pub cc_task: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Row {
+    dys_date: String,
+    total_time: String,
+    billable_time: String,
+    pma_person_code: String,
+    comment: String,
+}
+
+/// Reads exports of a single project.
+pub struct CsvImport {
+    persons: PersonTable,
+    project: ProjectMapping,
+}
+
+impl CsvImport {
+    pub fn new(persons: PersonTable, project: ProjectMapping) -> Self {
+        Self { persons, project }
+    }
+
+    pub fn read_file(&self, path: &Path) -> Result<Vec<TimeEntry>, Error> {
+        self.read(open(path)?)
+    }
+
+    /// Reads all rows of an export, in the order of the file.
+    ///
+    /// Exports have no times of day, so rows of a person on a day with the same comment share
+    /// their fingerprint. The migration tells them apart by their order, see
+    /// [`crate::migration::plan_entries`].
+    pub fn read(&self, reader: impl Read) -> Result<Vec<TimeEntry>, Error> {
+        let mut reader = csv::Reader::from_reader(reader);
+        let headers = reader.headers().context(ReadCsvSnafu)?.clone();
+        let mut entries = Vec::new();
+
+        for record in reader.records() {
+            let record = record.context(ReadCsvSnafu)?;
+            let line = record.position().map_or(0, |position| position.line());
+            let row: Row = record.deserialize(Some(&headers)).context(ReadCsvSnafu)?;
+
+            let date = parse_date(&row.dys_date).context(InvalidValueSnafu {
+                field: "dys_date",
+                value: &row.dys_date,
+                line,
+            })?;
+            let total_time = parse_decimal(&row.total_time).context(InvalidValueSnafu {
+                field: "total_time",
+                value: &row.total_time,
+                line,
+            })?;
+            let billable_time = parse_decimal(&row.billable_time).context(InvalidValueSnafu {
+                field: "billable_time",
+                value: &row.billable_time,
+                line,
+            })?;
+            let person = self
+                .persons
+                .get(&row.pma_person_code)
+                .context(UnknownPersonSnafu {
+                    code: &row.pma_person_code,
+                    line,
+                })?;
+
+            entries.push(TimeEntry {
+                date,
+                start_time: None,
+                end_time: None,
+                total_time,
+                billable_time,
+                cc_project: self.project.cc_project.clone(),
+                ban: self.project.ban.clone(),
+                cc_id: person.cc_id.clone(),
+                cc_team: person.cc_team.clone(),
+                cc_task: self.project.cc_task.clone(),
+                comment: row.comment,
+            });
+        }
+        Ok(entries)
+    }
+}
+
+fn open(path: &Path) -> Result<std::fs::File, Error> {
+    std::fs::File::open(path).context(OpenFileSnafu {
+        path: path.display().to_string(),
+    })
+}
+
+/// Parses `0,5`, `1.234,5` as well as `0.5`.
+fn parse_decimal(value: &str) -> Option<f64> {
+    let value = value.trim();
+    let normalized = if value.contains(',') {
+        value.replace('.', "").replace(',', ".")
+    } else {
+        value.to_string()
+    };
+    normalized
+        .parse()
+        .ok()
+        .filter(|value: &f64| value.is_finite())
+}
+
+/// Parses `2025-02-20` as well as `20.02.2025`.
+fn parse_date(value: &str) -> Option<NaiveDate> {
+    let value = value.trim();
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .or_else(|_| NaiveDate::parse_from_str(value, "%d.%m.%Y"))
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::import::{
+        parse_date, parse_decimal, CsvImport, Error, Person, PersonTable, ProjectMapping,
+    };
+    use chrono::NaiveDate;
+    use std::path::Path;
+
+    fn import() -> CsvImport {
+        let persons =
+            PersonTable::from_csv("code,cc_id,cc_team\nSIWE,42,3\nANKE,43,3\n".as_bytes()).unwrap();
+        CsvImport::new(
+            persons,
+            ProjectMapping {
+                ban: "001105".to_string(),
+                cc_project: "10".to_string(),
+                cc_task: "210".to_string(),
+            },
+        )
+    }
+
+    #[test]
+    fn test_parse_decimal() {
+        assert_eq!(parse_decimal("0,5"), Some(0.5));
+        assert_eq!(parse_decimal("2"), Some(2.0));
+        assert_eq!(parse_decimal("1.234,75"), Some(1234.75));
+        assert_eq!(parse_decimal("0.25"), Some(0.25));
+        assert_eq!(parse_decimal("zwei"), None);
+        assert_eq!(parse_decimal("NaN"), None);
+    }
+
+    #[test]
+    fn test_parse_date() {
+        let date = NaiveDate::from_ymd_opt(2025, 2, 20);
+        assert_eq!(parse_date("2025-02-20"), date);
+        assert_eq!(parse_date("20.02.2025"), date);
+        assert_eq!(parse_date("02/20/2025"), None);
+    }
+
+    #[test]
+    fn test_read_example() {
+        let entries = import()
+            .read_file(Path::new("examples/project_times.csv"))
+            .unwrap();
+
+        assert_eq!(entries.len(), 64);
+        let first = &entries[0];
+        assert_eq!(first.date, NaiveDate::from_ymd_opt(2024, 12, 12).unwrap());
+        assert_eq!(first.total_time, 0.5);
+        assert_eq!(first.cc_id, "42");
+        assert_eq!(first.ban, "001105");
+        assert_eq!(
+            first.comment,
+            "Kafka mit Kerberos und Active Directory getestet"
+        );
+    }
+
+    #[test]
+    fn test_entries_have_no_times() {
+        let csv = "dys_date,total_time,billable_time,pma_person_code,comment\n\
+            2025-03-17,\"1,5\",\"1,5\",SIWE,Meeting\n\
+            2025-03-17,\"0,5\",\"0,5\",SIWE,Meeting\n\
+            2025-03-17,\"0,1\",\"0,1\",ANKE,Meeting\n";
+
+        let entries = import().read(csv.as_bytes()).unwrap();
+
+        assert!(entries
+            .iter()
+            .all(|entry| entry.start_time.is_none() && entry.end_time.is_none()));
+        // Told apart by their order when migrating
+        assert_eq!(entries[0].fingerprint(), entries[1].fingerprint());
+        assert_ne!(entries[0].fingerprint(), entries[2].fingerprint());
+    }
+
+    #[test]
+    fn test_unknown_person() {
+        let csv = "dys_date,total_time,billable_time,pma_person_code,comment\n\
+            2025-03-17,1,1,SIWE,Meeting\n\
+            2025-03-18,1,1,MAMU,Meeting\n";
+
+        let error = import().read(csv.as_bytes()).unwrap_err();
+
+        assert!(matches!(error, Error::UnknownPerson { code, line: 3 } if code == "MAMU"));
+    }
+
+    #[test]
+    fn test_invalid_decimal() {
+        let csv = "dys_date,total_time,billable_time,pma_person_code,comment\n\
+            2025-03-17,\"1,5h\",1,SIWE,Meeting\n";
+
+        let error = import().read(csv.as_bytes()).unwrap_err();
+
+        assert_eq!(error.to_string(), "Invalid total_time [1,5h] in line 2");
+    }
+
+    #[test]
+    fn test_person_table() {
+        let persons = PersonTable::new([Person {
+            code: "SIWE".to_string(),
+            cc_id: "42".to_string(),
+            cc_team: "3".to_string(),
+        }]);
+
+        assert_eq!(persons.get("SIWE").unwrap().cc_id, "42");
+        assert!(persons.get("ANKE").is_none());
+    }
+}
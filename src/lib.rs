@@ -1,7 +1,12 @@
+pub mod baserow;
 pub mod easybill;
 pub mod generated;
 pub mod bdwh;
 pub mod coffeecup;
+pub mod dates;
+pub mod import;
+pub mod migration;
+pub mod offers;
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,956 @@
+use crate::bdwh::structs::{EntryFilter, TimeEntry as BdwhTimeEntry};
+use crate::bdwh::{self, Bdwh};
+use crate::coffeecup::client::{self, CoffeeCup};
+use crate::coffeecup::conversion::ConversionPolicy;
+use crate::coffeecup::resolver::{self, Resolver};
+use crate::coffeecup::structs::{TimeEntry, TimeEntryFilter};
+use chrono::NaiveDate;
+use futures::{StreamExt, TryStreamExt};
+use serde::{Serialize, Serializer};
+use snafu::{ResultExt, Snafu};
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::pin::pin;
+
+/// Number of external IDs looked up in CoffeeCup per request, which keeps the URL short.
+const EXTERNAL_ID_CHUNK: usize = 50;
+
+/// Number of requests for existing entries that are sent at the same time.
+const LOOKUP_CONCURRENCY: usize = 8;
+
+#[derive(Snafu, Debug)]
+pub enum Error {
+    #[snafu(display("Failed to read time entries from BDWH: {source}"))]
+    ReadBdwh { source: bdwh::Error },
+    #[snafu(display("Failed to read existing time entries from CoffeeCup: {source}"))]
+    ReadCoffeeCup { source: client::Error },
+    #[snafu(display("Failed to load projects, tasks, users and teams from CoffeeCup: {source}"))]
+    LoadMappings { source: client::Error },
+}
+
+/// A BDWH row that can't be migrated, because it refers to something unknown to CoffeeCup or
+/// can't be converted.
+#[derive(Debug, Serialize)]
+pub struct UnresolvedEntry {
+    pub date: NaiveDate,
+    pub ban: String,
+    #[serde(serialize_with = "serialize_display")]
+    pub error: resolver::Error,
+}
+
+/// An entry CoffeeCup refused to create.
+#[derive(Debug, Serialize)]
+pub struct FailedEntry {
+    pub entry: TimeEntry,
+    #[serde(serialize_with = "serialize_display")]
+    pub error: client::Error,
+}
+
+/// Outcome of migrating time entries from BDWH to CoffeeCup.
+#[derive(Debug, Default, Serialize)]
+pub struct MigrationReport {
+    /// Entries that have been created in CoffeeCup by this run, as returned by CoffeeCup
+    pub created: Vec<TimeEntry>,
+    /// Entries that already existed in CoffeeCup, usually from an earlier run
+    pub skipped: Vec<TimeEntry>,
+    /// Entries that already existed in CoffeeCup, but have been changed on either side since.
+    /// They are left as they are.
+    pub changed: Vec<ChangedEntry>,
+    /// Entries that have not been created, because their row has the same fingerprint as
+    /// another row
+    pub colliding: Vec<TimeEntry>,
+    pub failed: Vec<FailedEntry>,
+    /// Entries in CoffeeCup that carry the same external ID as another entry, see
+    /// [`MigrationPlan::duplicated`]
+    pub duplicated: Vec<TimeEntry>,
+    /// Rows that could not be mapped to CoffeeCup and have not been attempted
+    pub unresolved: Vec<UnresolvedEntry>,
+}
+
+impl MigrationReport {
+    /// Adds the outcome of migrating another batch of entries.
+    pub fn extend(&mut self, other: MigrationReport) {
+        self.created.extend(other.created);
+        self.skipped.extend(other.skipped);
+        self.changed.extend(other.changed);
+        self.colliding.extend(other.colliding);
+        self.failed.extend(other.failed);
+        self.duplicated.extend(other.duplicated);
+        self.unresolved.extend(other.unresolved);
+    }
+}
+
+impl Display for MigrationReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for failed in &self.failed {
+            writeln!(
+                f,
+                "Failed to create entry for {} on {}: {}",
+                failed.entry.user, failed.entry.day, failed.error
+            )?;
+        }
+        for changed in &self.changed {
+            writeln!(
+                f,
+                "Not updating entry for {} on {}, it has been changed since it was migrated",
+                changed.source.user, changed.source.day
+            )?;
+        }
+        for colliding in &self.colliding {
+            writeln!(
+                f,
+                "Not creating entry for {} on {}, its row has the same fingerprint as another row",
+                colliding.user, colliding.day
+            )?;
+        }
+        for duplicated in &self.duplicated {
+            writeln!(
+                f,
+                "Entry {} for {} on {} has the same external ID as another entry",
+                duplicated.id.unwrap_or_default(),
+                duplicated.user,
+                duplicated.day
+            )?;
+        }
+        for unresolved in &self.unresolved {
+            writeln!(f, "{}", unresolved.error)?;
+        }
+        write!(
+            f,
+            "{} created, {} skipped, {} changed, {} colliding, {} failed, {} duplicated, {} \
+             unresolved",
+            self.created.len(),
+            self.skipped.len(),
+            self.changed.len(),
+            self.colliding.len(),
+            self.failed.len(),
+            self.duplicated.len(),
+            self.unresolved.len()
+        )
+    }
+}
+
+fn serialize_display<S>(value: &impl Display, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.collect_str(value)
+}
+
+/// An entry that has been migrated before, but has since been changed on either side.
+#[derive(Debug, Serialize)]
+pub struct ChangedEntry {
+    /// The entry as it currently is in CoffeeCup
+    pub existing: TimeEntry,
+    /// The entry as it would be created from BDWH today
+    pub source: TimeEntry,
+}
+
+/// What a migration would do, computed without writing anything to CoffeeCup.
+#[derive(Debug, Default, Serialize)]
+pub struct MigrationPlan {
+    /// Entries that are missing in CoffeeCup
+    pub to_create: Vec<TimeEntry>,
+    /// Entries that exist in CoffeeCup but differ in project, task, duration, comment or
+    /// billable flag
+    pub changed: Vec<ChangedEntry>,
+    /// Entries that exist in CoffeeCup exactly as they would be created
+    pub unchanged: Vec<TimeEntry>,
+    /// Entries of rows that have the same fingerprint as an earlier row, such as identical
+    /// rows, and would end up as the same CoffeeCup entry
+    pub colliding: Vec<TimeEntry>,
+    /// Entries in CoffeeCup for the same projects and days without an external ID, such as
+    /// entries created by hand.
+    ///
+    /// Entries with an external ID that none of the rows has are left out, as they have been
+    /// created by another integration or from rows of another BAN booked on the same project.
+    pub orphaned: Vec<TimeEntry>,
+    /// Entries in CoffeeCup that carry the same external ID as another entry, for example
+    /// because two migrations ran at the same time. Only the other entry is compared with the
+    /// row.
+    pub duplicated: Vec<TimeEntry>,
+    /// Rows that could not be mapped to CoffeeCup
+    pub unresolved: Vec<UnresolvedEntry>,
+}
+
+impl MigrationPlan {
+    /// Adds the plan for another batch of entries.
+    pub fn extend(&mut self, other: MigrationPlan) {
+        self.to_create.extend(other.to_create);
+        self.changed.extend(other.changed);
+        self.unchanged.extend(other.unchanged);
+        self.colliding.extend(other.colliding);
+        self.orphaned.extend(other.orphaned);
+        self.duplicated.extend(other.duplicated);
+        self.unresolved.extend(other.unresolved);
+    }
+}
+
+impl Display for MigrationPlan {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{:<10} {:<10} {:>6} {:>8} {:>6} {:>9}  COMMENT",
+            "ACTION", "DAY", "USER", "PROJECT", "TASK", "HOURS"
+        )?;
+        for entry in &self.to_create {
+            write_row(f, "create", entry)?;
+        }
+        for changed in &self.changed {
+            write_row(f, "changed", &changed.source)?;
+            write_row(f, "  was", &changed.existing)?;
+        }
+        for entry in &self.colliding {
+            write_row(f, "colliding", entry)?;
+        }
+        for entry in &self.orphaned {
+            write_row(f, "orphaned", entry)?;
+        }
+        for entry in &self.duplicated {
+            write_row(f, "duplicated", entry)?;
+        }
+        for unresolved in &self.unresolved {
+            writeln!(
+                f,
+                "{:<10} {:<10} {}",
+                "unresolved",
+                unresolved.date.to_string(),
+                unresolved.error
+            )?;
+        }
+        write!(
+            f,
+            "{} to create, {} changed, {} unchanged, {} colliding, {} orphaned, {} duplicated, {} \
+             unresolved",
+            self.to_create.len(),
+            self.changed.len(),
+            self.unchanged.len(),
+            self.colliding.len(),
+            self.orphaned.len(),
+            self.duplicated.len(),
+            self.unresolved.len()
+        )
+    }
+}
+
+fn write_row(f: &mut Formatter<'_>, action: &str, entry: &TimeEntry) -> std::fmt::Result {
+    writeln!(
+        f,
+        "{:<10} {:<10} {:>6} {:>8} {:>6} {:>9.2}  {}",
+        action,
+        entry.day.to_string(),
+        entry.user,
+        entry.project,
+        entry.task,
+        entry.duration as f64 / 3600.0,
+        entry.comment.as_deref().unwrap_or_default()
+    )
+}
+
+/// Copies time entries booked in BDWH over to CoffeeCup.
+///
+/// Every CoffeeCup entry carries the fingerprint of the BDWH row it was created from as
+/// `externalId`, so running a migration again only creates entries that are still missing.
+pub struct Migrator<'a> {
+    bdwh: &'a Bdwh,
+    coffeecup: &'a CoffeeCup,
+    policy: ConversionPolicy,
+}
+
+impl<'a> Migrator<'a> {
+    pub fn new(bdwh: &'a Bdwh, coffeecup: &'a CoffeeCup) -> Self {
+        Self {
+            bdwh,
+            coffeecup,
+            policy: ConversionPolicy::default(),
+        }
+    }
+
+    /// Converts rows as decided by `policy` instead of the default policy.
+    pub fn with_policy(mut self, policy: ConversionPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Computes what migrating `ban` would do, without changing anything in CoffeeCup.
+    pub async fn plan_ban(&self, ban: &str) -> Result<MigrationPlan, Error> {
+        self.plan(&EntryFilter::default().ban(ban)).await
+    }
+
+    /// Migrates all time entries booked on `ban`.
+    pub async fn migrate_ban(&self, ban: &str) -> Result<MigrationReport, Error> {
+        self.migrate(&EntryFilter::default().ban(ban)).await
+    }
+
+    /// Computes what migrating the rows matching `filter` would do, without changing anything
+    /// in CoffeeCup.
+    ///
+    /// Rows are read and planned month by month. Fingerprints include the day and existing
+    /// entries are looked up per day, so a month is planned the same as it would be together
+    /// with the other months.
+    pub async fn plan(&self, filter: &EntryFilter) -> Result<MigrationPlan, Error> {
+        let resolver = Resolver::load(self.coffeecup)
+            .await
+            .context(LoadMappingsSnafu)?;
+        let mut batches = pin!(self
+            .bdwh
+            .entries_by_month(filter)
+            .await
+            .context(ReadBdwhSnafu)?);
+
+        let mut plan = MigrationPlan::default();
+        while let Some(batch) = batches.try_next().await.context(ReadBdwhSnafu)? {
+            plan.extend(plan_entries(self.coffeecup, &resolver, &self.policy, batch).await?);
+        }
+        Ok(plan)
+    }
+
+    /// Migrates the rows matching `filter`, for example a single month.
+    ///
+    /// Rows are read and migrated month by month, so a month is written to CoffeeCup while
+    /// the next one is still to be read.
+    pub async fn migrate(&self, filter: &EntryFilter) -> Result<MigrationReport, Error> {
+        let resolver = Resolver::load(self.coffeecup)
+            .await
+            .context(LoadMappingsSnafu)?;
+        let mut batches = pin!(self
+            .bdwh
+            .entries_by_month(filter)
+            .await
+            .context(ReadBdwhSnafu)?);
+
+        let mut report = MigrationReport::default();
+        while let Some(batch) = batches.try_next().await.context(ReadBdwhSnafu)? {
+            let plan = plan_entries(self.coffeecup, &resolver, &self.policy, batch).await?;
+            report.extend(apply_plan(self.coffeecup, plan).await);
+        }
+        Ok(report)
+    }
+}
+
+/// Compares `entries` with what already exists in CoffeeCup.
+///
+/// Rows `resolver` can't map to CoffeeCup are listed as unresolved and otherwise ignored, the
+/// others are converted as decided by `policy`.
+///
+/// Rows without times of day share their fingerprint with the other rows of the person on the
+/// day with the same comment. The second and later of them get their position appended to the
+/// external ID, so they are told apart regardless of where other rows are in `entries`.
+pub async fn plan_entries(
+    coffeecup: &CoffeeCup,
+    resolver: &Resolver,
+    policy: &ConversionPolicy,
+    entries: Vec<BdwhTimeEntry>,
+) -> Result<MigrationPlan, Error> {
+    let mut plan = MigrationPlan::default();
+    let mut sources: Vec<TimeEntry> = Vec::new();
+    let mut occurrences: HashMap<String, usize> = HashMap::new();
+    for entry in entries {
+        let (date, ban) = (entry.date, entry.ban.clone());
+        let occurrence = match entry.start_time {
+            Some(_) => 1,
+            None => {
+                let occurrence = occurrences.entry(entry.fingerprint()).or_default();
+                *occurrence += 1;
+                *occurrence
+            }
+        };
+        match resolver.to_timeentries(entry, policy) {
+            Ok(mut converted) => {
+                if occurrence > 1 {
+                    for source in &mut converted {
+                        source.externalId = source
+                            .externalId
+                            .as_ref()
+                            .map(|external_id| format!("{external_id}-{occurrence}"));
+                    }
+                }
+                sources.extend(converted)
+            }
+            Err(error) => plan.unresolved.push(UnresolvedEntry { date, ban, error }),
+        }
+    }
+
+    let external_ids: HashSet<String> = sources
+        .iter()
+        .filter_map(|source| source.externalId.clone())
+        .collect();
+    let mut existing: HashMap<String, TimeEntry> = HashMap::new();
+    for entry in existing_entries(coffeecup, &sources).await? {
+        match &entry.externalId {
+            None => plan.orphaned.push(entry),
+            // Created by another integration or from a row of another BAN
+            Some(external_id) if !external_ids.contains(external_id) => {}
+            Some(external_id) => match existing.entry(external_id.clone()) {
+                Entry::Vacant(vacant) => {
+                    vacant.insert(entry);
+                }
+                Entry::Occupied(_) => plan.duplicated.push(entry),
+            },
+        }
+    }
+
+    let mut planned: HashSet<String> = HashSet::new();
+    for source in sources {
+        // Converted entries always carry the fingerprint of their row
+        let external_id = source.externalId.clone().unwrap_or_default();
+        // The fingerprint leaves out hours, task and project, so rows that share it may
+        // differ in those. Only the first one can be migrated.
+        if !planned.insert(external_id.clone()) {
+            plan.colliding.push(source);
+            continue;
+        }
+        match existing.remove(&external_id) {
+            None => plan.to_create.push(source),
+            Some(existing) if differs(&existing, &source) => {
+                plan.changed.push(ChangedEntry { existing, source })
+            }
+            Some(_) => plan.unchanged.push(source),
+        }
+    }
+    plan.orphaned.sort_by_key(|entry| (entry.day, entry.id));
+
+    Ok(plan)
+}
+
+/// Creates the entries in `plan` that are missing in CoffeeCup.
+///
+/// Changed and orphaned entries are left alone. Failing to create a single entry does not
+/// abort the migration, the error is recorded in the returned report instead.
+pub async fn apply_plan(coffeecup: &CoffeeCup, plan: MigrationPlan) -> MigrationReport {
+    let mut report = MigrationReport {
+        skipped: plan.unchanged,
+        changed: plan.changed,
+        colliding: plan.colliding,
+        duplicated: plan.duplicated,
+        unresolved: plan.unresolved,
+        ..MigrationReport::default()
+    };
+
+    let results = coffeecup.create_timeentries(&plan.to_create).await;
+    for (entry, result) in plan.to_create.into_iter().zip(results) {
+        match result {
+            Ok(created) => report.created.push(created),
+            Err(error) => report.failed.push(FailedEntry { entry, error }),
+        }
+    }
+    report
+}
+
+/// Creates an entry in CoffeeCup for every row in `entries` that has not been migrated yet.
+pub async fn migrate_entries(
+    coffeecup: &CoffeeCup,
+    resolver: &Resolver,
+    policy: &ConversionPolicy,
+    entries: Vec<BdwhTimeEntry>,
+) -> Result<MigrationReport, Error> {
+    let plan = plan_entries(coffeecup, resolver, policy, entries).await?;
+
+    Ok(apply_plan(coffeecup, plan).await)
+}
+
+fn differs(existing: &TimeEntry, source: &TimeEntry) -> bool {
+    existing.project != source.project
+        || existing.task != source.task
+        || existing.duration != source.duration
+        || existing.comment.as_deref().unwrap_or_default()
+            != source.comment.as_deref().unwrap_or_default()
+        || source
+            .billable
+            .is_some_and(|billable| existing.billable != Some(billable))
+}
+
+/// Returns the CoffeeCup entries that have been migrated from `sources` before, as well as
+/// all other entries on the projects of `sources` on the days they are booked on.
+///
+/// Migrated entries are looked up by their external ID, as they may be booked on another
+/// project than the one a row maps to today, if the mapping of its BAN has been changed since.
+async fn existing_entries(
+    coffeecup: &CoffeeCup,
+    sources: &[TimeEntry],
+) -> Result<Vec<TimeEntry>, Error> {
+    let mut days: BTreeMap<NaiveDate, Vec<usize>> = BTreeMap::new();
+    for source in sources {
+        days.entry(source.day).or_default().push(source.project);
+    }
+    let mut external_ids: Vec<String> = sources
+        .iter()
+        .filter_map(|entry| entry.externalId.clone())
+        .collect();
+    external_ids.sort();
+    external_ids.dedup();
+
+    // Days are looked up one by one, as rows of a BAN are often spread over a long time
+    let mut filters: Vec<TimeEntryFilter> = days
+        .into_iter()
+        .map(|(day, mut projects)| {
+            projects.sort();
+            projects.dedup();
+            TimeEntryFilter {
+                projects: Some(projects),
+                from: Some(day),
+                to: Some(day),
+                ..TimeEntryFilter::default()
+            }
+        })
+        .collect();
+    filters.extend(
+        external_ids
+            .chunks(EXTERNAL_ID_CHUNK)
+            .map(|chunk| TimeEntryFilter {
+                external_ids: Some(chunk.to_vec()),
+                ..TimeEntryFilter::default()
+            }),
+    );
+    let found: Vec<Vec<TimeEntry>> = futures::stream::iter(&filters)
+        .map(|filter| coffeecup.get_timeentries(filter))
+        .buffered(LOOKUP_CONCURRENCY)
+        .try_collect()
+        .await
+        .context(ReadCoffeeCupSnafu)?;
+
+    // Migrated entries on the same projects and days are found by both lookups
+    let mut seen: HashSet<Option<usize>> = HashSet::new();
+    Ok(found
+        .into_iter()
+        .flatten()
+        .filter(|entry| entry.id.is_none() || seen.insert(entry.id))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::coffeecup::conversion::{Billing, ConversionPolicy};
+    use crate::coffeecup::mock::{
+        bdwh_entry, client_for, fixture, mock_server, resolver, timeentry_response,
+    };
+    use crate::coffeecup::structs::TimeEntryFilter;
+    use crate::migration::{migrate_entries, plan_entries};
+    use chrono::{NaiveDate, NaiveTime};
+    use serde_json::{json, Value};
+    use wiremock::matchers::{body_partial_json, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    /// Serves the time entry fixtures, with the first one marked as migrated from `external_id`.
+    async fn mock_existing_entries(server: &MockServer, external_id: &str) {
+        let mut existing: Value = serde_json::from_str(&fixture("list_timeentries.json")).unwrap();
+        existing["timeEntries"][0]["externalId"] = json!(external_id);
+        Mock::given(method("GET"))
+            .and(path("/v1/timeentries"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(existing))
+            .mount(server)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn test_migrate_skips_existing_entries() {
+        let server = mock_server().await;
+        // Exactly as the first existing entry
+        let mut migrated = bdwh_entry();
+        migrated.end_time = NaiveTime::from_hms_opt(10, 30, 0);
+        migrated.total_time = 1.5;
+        migrated.billable_time = 1.5;
+        let mut missing = bdwh_entry();
+        missing.date = NaiveDate::from_ymd_opt(2025, 6, 3).unwrap();
+        mock_existing_entries(&server, &migrated.fingerprint()).await;
+        Mock::given(method("POST"))
+            .and(path("/v1/timeentries"))
+            .and(body_partial_json(json!({
+                "timeEntry": { "day": "2025-06-03", "externalId": missing.fingerprint() }
+            })))
+            .respond_with(timeentry_response(201))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let cc_client = client_for(&server).await;
+
+        let report = migrate_entries(
+            &cc_client,
+            &resolver(),
+            &ConversionPolicy::default(),
+            vec![migrated, missing],
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(report.created.len(), 1);
+        assert_eq!(report.skipped.len(), 1);
+        assert!(report.changed.is_empty());
+        assert!(report.failed.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_plan_finds_entries_after_project_rebinding() {
+        let server = mock_server().await;
+        let mut rebound = bdwh_entry();
+        rebound.cc_project = "11".to_string();
+        rebound.cc_task = "211".to_string();
+        let mut migrated: Value = serde_json::from_str(&fixture("list_timeentries.json")).unwrap();
+        // Migrated while the BAN still mapped to project 10
+        migrated["timeEntries"].as_array_mut().unwrap().truncate(1);
+        migrated["timeEntries"][0]["externalId"] = json!(rebound.fingerprint());
+        migrated["meta"]["total"] = json!(1);
+        Mock::given(method("GET"))
+            .and(path("/v1/timeentries"))
+            .and(query_param(
+                "where",
+                json!({ "externalId": { "in": [rebound.fingerprint()] } }).to_string(),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(migrated))
+            .mount(&server)
+            .await;
+        // Nothing is booked on the project the BAN maps to now
+        Mock::given(method("GET"))
+            .and(path("/v1/timeentries"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "timeEntries": [],
+                "meta": { "skip": 0, "limit": 500, "total": 0 }
+            })))
+            .mount(&server)
+            .await;
+        let cc_client = client_for(&server).await;
+
+        let plan = plan_entries(
+            &cc_client,
+            &resolver(),
+            &ConversionPolicy::default(),
+            vec![rebound],
+        )
+        .await
+        .unwrap();
+
+        assert!(plan.to_create.is_empty());
+        assert_eq!(plan.changed.len(), 1);
+        assert_eq!(plan.changed[0].existing.project, 10);
+        assert_eq!(plan.changed[0].source.project, 11);
+        assert!(plan.orphaned.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_plan_numbers_rows_without_times() {
+        let server = mock_server().await;
+        let timeless = |total_time: f64| {
+            let mut entry = bdwh_entry();
+            entry.start_time = None;
+            entry.end_time = None;
+            entry.total_time = total_time;
+            entry.billable_time = total_time;
+            entry
+        };
+        let mut other_comment = timeless(1.0);
+        other_comment.comment = "Review".to_string();
+        let fingerprint = timeless(0.5).fingerprint();
+        // Migrated from an export without the row with another comment
+        mock_existing_entries(&server, &format!("{fingerprint}-2")).await;
+        let cc_client = client_for(&server).await;
+
+        let plan = plan_entries(
+            &cc_client,
+            &resolver(),
+            &ConversionPolicy::default(),
+            vec![other_comment, timeless(0.5), timeless(1.5)],
+        )
+        .await
+        .unwrap();
+
+        let external_ids: Vec<&str> = plan
+            .to_create
+            .iter()
+            .map(|entry| entry.externalId.as_deref().unwrap())
+            .collect();
+        assert_eq!(external_ids.len(), 2);
+        assert_eq!(external_ids[1], fingerprint);
+        // The existing entry has the 1.5h of the second row
+        assert_eq!(plan.unchanged.len(), 1);
+        assert!(plan.colliding.is_empty());
+        assert!(plan.to_create.iter().all(|entry| entry.startTime.is_none()));
+    }
+
+    #[tokio::test]
+    async fn test_migrate_reports_colliding_rows() {
+        let server = mock_server().await;
+        // Same person, BAN, times and comment, but different hours
+        let mut colliding = bdwh_entry();
+        colliding.total_time = 1.0;
+        colliding.billable_time = 1.0;
+        mock_existing_entries(&server, "bdwh-unrelated").await;
+        Mock::given(method("POST"))
+            .and(path("/v1/timeentries"))
+            .respond_with(timeentry_response(201))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let cc_client = client_for(&server).await;
+
+        let report = migrate_entries(
+            &cc_client,
+            &resolver(),
+            &ConversionPolicy::default(),
+            vec![bdwh_entry(), colliding],
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(report.created.len(), 1);
+        assert!(report.skipped.is_empty());
+        assert_eq!(report.colliding.len(), 1);
+        assert_eq!(report.colliding[0].duration, 3600);
+        assert!(report.to_string().ends_with(
+            "1 created, 0 skipped, 0 changed, 1 colliding, 0 failed, 0 duplicated, 0 unresolved"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_migrate_reports_changed_entries() {
+        let server = mock_server().await;
+        // Migrated with 1.5h, the row has 0.5h by now
+        let changed = bdwh_entry();
+        mock_existing_entries(&server, &changed.fingerprint()).await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(201))
+            .expect(0)
+            .mount(&server)
+            .await;
+        let cc_client = client_for(&server).await;
+
+        let report = migrate_entries(
+            &cc_client,
+            &resolver(),
+            &ConversionPolicy::default(),
+            vec![changed],
+        )
+        .await
+        .unwrap();
+
+        assert!(report.created.is_empty());
+        assert!(report.skipped.is_empty());
+        assert_eq!(report.changed.len(), 1);
+        assert_eq!(report.changed[0].existing.id, Some(9120001));
+        let summary = report.to_string();
+        assert!(summary.contains("has been changed since it was migrated"));
+        assert!(summary.ends_with(
+            "0 created, 0 skipped, 1 changed, 0 colliding, 0 failed, 0 duplicated, 0 unresolved"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_migrate_reports_failed_entries() {
+        let server = mock_server().await;
+        mock_existing_entries(&server, "bdwh-unrelated").await;
+        Mock::given(method("POST"))
+            .and(path("/v1/timeentries"))
+            .respond_with(ResponseTemplate::new(400))
+            .mount(&server)
+            .await;
+        let cc_client = client_for(&server).await;
+
+        let report = migrate_entries(
+            &cc_client,
+            &resolver(),
+            &ConversionPolicy::default(),
+            vec![bdwh_entry()],
+        )
+        .await
+        .unwrap();
+
+        assert!(report.created.is_empty());
+        assert_eq!(report.failed.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_migrate_splits_partly_billable_rows() {
+        let server = mock_server().await;
+        let mut entry = bdwh_entry();
+        entry.billable_time = 0.1;
+        mock_existing_entries(&server, "bdwh-unrelated").await;
+        for (billable, duration) in [(true, 360), (false, 1440)] {
+            Mock::given(method("POST"))
+                .and(path("/v1/timeentries"))
+                .and(body_partial_json(json!({
+                    "timeEntry": { "billable": billable, "duration": duration }
+                })))
+                .respond_with(timeentry_response(201))
+                .expect(1)
+                .mount(&server)
+                .await;
+        }
+        let cc_client = client_for(&server).await;
+        let policy = ConversionPolicy {
+            billing: Billing::Split,
+            ..ConversionPolicy::default()
+        };
+
+        let report = migrate_entries(&cc_client, &resolver(), &policy, vec![entry])
+            .await
+            .unwrap();
+
+        assert_eq!(report.created.len(), 2);
+        assert!(report.failed.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_plan_without_writing() {
+        let server = mock_server().await;
+        // The first existing entry was migrated from this row, it has 1.5h in CoffeeCup while
+        // the row has 0.5h by now
+        let changed = bdwh_entry();
+        let mut missing = bdwh_entry();
+        missing.date = NaiveDate::from_ymd_opt(2025, 6, 4).unwrap();
+        let mut unknown_task = bdwh_entry();
+        unknown_task.date = NaiveDate::from_ymd_opt(2025, 6, 5).unwrap();
+        unknown_task.cc_task = "Training".to_string();
+        mock_existing_entries(&server, &changed.fingerprint()).await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(201))
+            .expect(0)
+            .mount(&server)
+            .await;
+        let cc_client = client_for(&server).await;
+
+        let plan = plan_entries(
+            &cc_client,
+            &resolver(),
+            &ConversionPolicy::default(),
+            vec![changed, missing, unknown_task],
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(plan.to_create.len(), 1);
+        assert_eq!(plan.to_create[0].day.to_string(), "2025-06-04");
+        assert_eq!(plan.changed.len(), 1);
+        assert_eq!(plan.changed[0].existing.duration, 5400);
+        assert_eq!(plan.changed[0].source.duration, 1800);
+        assert!(plan.unchanged.is_empty());
+        // Created by hand in CoffeeCup
+        assert_eq!(plan.orphaned.len(), 1);
+        assert_eq!(plan.orphaned[0].id, Some(9120002));
+        assert_eq!(plan.unresolved.len(), 1);
+
+        let table = plan.to_string();
+        assert!(table.contains("create     2025-06-04"));
+        assert!(table.contains("changed    2025-06-02"));
+        assert!(table.contains("orphaned   2025-06-03"));
+        assert!(table.contains("unresolved 2025-06-05 Found no CoffeeCup task matching [Training]"));
+        assert!(table.ends_with(
+            "1 to create, 1 changed, 0 unchanged, 0 colliding, 1 orphaned, 0 duplicated, 1 \
+             unresolved"
+        ));
+
+        let plan_json = serde_json::to_value(&plan).unwrap();
+        assert_eq!(plan_json["changed"][0]["existing"]["id"], 9120001);
+        assert_eq!(plan_json["orphaned"][0]["id"], 9120002);
+    }
+
+    /// Serves the time entry fixtures with the given external IDs.
+    async fn mock_entries_with_external_ids(server: &MockServer, external_ids: [&str; 2]) {
+        let mut existing: Value = serde_json::from_str(&fixture("list_timeentries.json")).unwrap();
+        for (entry, external_id) in external_ids.iter().enumerate() {
+            existing["timeEntries"][entry]["externalId"] = json!(external_id);
+        }
+        Mock::given(method("GET"))
+            .and(path("/v1/timeentries"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(existing))
+            .mount(server)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn test_plan_leaves_out_entries_of_other_sources() {
+        let server = mock_server().await;
+        mock_entries_with_external_ids(
+            &server,
+            // Migrated from a row of another BAN on the same project, and by another tool
+            ["bdwh-5d41402abc4b2a76b9719d911017c592", "jira-ENG-1234"],
+        )
+        .await;
+        let cc_client = client_for(&server).await;
+
+        let plan = plan_entries(
+            &cc_client,
+            &resolver(),
+            &ConversionPolicy::default(),
+            vec![bdwh_entry()],
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(plan.to_create.len(), 1);
+        assert!(plan.orphaned.is_empty());
+        assert!(plan.duplicated.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_plan_reports_duplicated_external_ids() {
+        let server = mock_server().await;
+        let entry = bdwh_entry();
+        mock_entries_with_external_ids(&server, [&entry.fingerprint(), &entry.fingerprint()]).await;
+        let cc_client = client_for(&server).await;
+
+        let plan = plan_entries(
+            &cc_client,
+            &resolver(),
+            &ConversionPolicy::default(),
+            vec![entry],
+        )
+        .await
+        .unwrap();
+
+        assert!(plan.to_create.is_empty());
+        assert_eq!(plan.changed.len(), 1);
+        assert_eq!(plan.changed[0].existing.id, Some(9120001));
+        assert_eq!(plan.duplicated.len(), 1);
+        assert_eq!(plan.duplicated[0].id, Some(9120002));
+        assert!(plan.orphaned.is_empty());
+        assert!(plan.to_string().contains("duplicated 2025-06-03"));
+    }
+
+    #[tokio::test]
+    async fn test_plan_looks_up_each_day() {
+        let server = mock_server().await;
+        let first = bdwh_entry();
+        let mut last = bdwh_entry();
+        last.date = NaiveDate::from_ymd_opt(2025, 6, 30).unwrap();
+        for day in [first.date, last.date] {
+            let filter = TimeEntryFilter {
+                projects: Some(vec![10]),
+                from: Some(day),
+                to: Some(day),
+                ..TimeEntryFilter::default()
+            };
+            Mock::given(method("GET"))
+                .and(path("/v1/timeentries"))
+                .and(query_param("where", filter.where_clause().to_string()))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                    "timeEntries": [],
+                    "meta": { "skip": 0, "limit": 500, "total": 0 }
+                })))
+                .expect(1)
+                .mount(&server)
+                .await;
+        }
+        // The lookup by external ID
+        Mock::given(method("GET"))
+            .and(path("/v1/timeentries"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "timeEntries": [],
+                "meta": { "skip": 0, "limit": 500, "total": 0 }
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let cc_client = client_for(&server).await;
+
+        let plan = plan_entries(
+            &cc_client,
+            &resolver(),
+            &ConversionPolicy::default(),
+            vec![first, last],
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(plan.to_create.len(), 2);
+    }
+}
@@ -0,0 +1,317 @@
+//! Keeps the offers table in Baserow in line with the offers written in easybill.
+
+use crate::baserow::client::Baserow;
+use crate::baserow::structs::Row;
+use crate::easybill::client::Easybill;
+use crate::easybill::structs::Document;
+use crate::generated::customersuccess::{Offers, OffersStatus};
+use crate::{baserow, easybill};
+use baserow_client::client::{BaserowObject, Identifier};
+use serde::Serialize;
+use snafu::{ResultExt, Snafu};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+#[derive(Snafu, Debug)]
+pub enum Error {
+    #[snafu(display("Failed to read offers from easybill: {source}"))]
+    ReadEasybill { source: easybill::client::Error },
+    #[snafu(display("Failed to read the offers table from Baserow: {source}"))]
+    ReadBaserow { source: baserow::client::Error },
+    #[snafu(display("Failed to write offer [{easybill_id}] to Baserow: {source}"))]
+    WriteBaserow {
+        source: baserow::client::Error,
+        easybill_id: usize,
+    },
+}
+
+/// An offer as it is written to Baserow.
+#[derive(Debug, Serialize)]
+pub struct SyncedOffer {
+    pub easybill_id: usize,
+    pub customer: String,
+    pub amount: f64,
+    pub status: String,
+}
+
+/// Outcome of syncing the offers from easybill to Baserow.
+#[derive(Debug, Default, Serialize)]
+pub struct OfferSyncReport {
+    /// Offers that were missing in Baserow
+    pub created: Vec<SyncedOffer>,
+    /// Offers whose customer, amount or status differed in Baserow
+    pub updated: Vec<SyncedOffer>,
+    /// Number of offers that were already up to date
+    pub unchanged: usize,
+    /// Set if nothing has been written to Baserow
+    pub dry_run: bool,
+}
+
+impl Display for OfferSyncReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (action, offers) in [("create", &self.created), ("update", &self.updated)] {
+            for offer in offers {
+                writeln!(
+                    f,
+                    "{:<8} {:>12}  {:>12.2}  {:<10} {}",
+                    action, offer.easybill_id, offer.amount, offer.status, offer.customer
+                )?;
+            }
+        }
+        write!(
+            f,
+            "{} created, {} updated, {} unchanged",
+            self.created.len(),
+            self.updated.len(),
+            self.unchanged
+        )?;
+        if self.dry_run {
+            write!(f, " (dry run, nothing has been written)")?;
+        }
+        Ok(())
+    }
+}
+
+/// Creates a row in the Baserow offers table for every easybill offer that has none, and
+/// updates rows whose customer, amount or status no longer match the offer.
+///
+/// Rows are matched to offers by their easybill ID, rows without one are left alone.
+pub async fn sync_offers(
+    easybill: &Easybill,
+    baserow: &Baserow,
+    dry_run: bool,
+) -> Result<OfferSyncReport, Error> {
+    let table_id = Offers::get_static_table_id();
+    let offers = easybill.list_offers().await.context(ReadEasybillSnafu)?;
+    let fields = baserow
+        .list_fields(table_id)
+        .await
+        .context(ReadBaserowSnafu)?;
+    let rows = baserow
+        .list_rows::<Offers>(table_id)
+        .await
+        .context(ReadBaserowSnafu)?;
+
+    let mut existing: HashMap<usize, Row<Offers>> = HashMap::new();
+    for row in rows {
+        if let Identifier::UnsignedNumber { id: Some(id) } = row.fields.get_id() {
+            existing.entry(id).or_insert(row);
+        }
+    }
+
+    let mut report = OfferSyncReport {
+        dry_run,
+        ..OfferSyncReport::default()
+    };
+    for offer in &offers {
+        let row = existing.get(&offer.id);
+        let desired = desired_row(offer, row.map(|row| &row.fields));
+        let write_context = WriteBaserowSnafu {
+            easybill_id: offer.id,
+        };
+        match row {
+            None => {
+                if !dry_run {
+                    baserow
+                        .create_row(table_id, &fields, &desired)
+                        .await
+                        .context(write_context)?;
+                }
+                report.created.push(synced(offer, &desired));
+            }
+            Some(row) if differs(&row.fields, &desired) => {
+                if !dry_run {
+                    baserow
+                        .update_row(table_id, row.id, &fields, &desired)
+                        .await
+                        .context(write_context)?;
+                }
+                report.updated.push(synced(offer, &desired));
+            }
+            Some(_) => report.unchanged += 1,
+        }
+    }
+    Ok(report)
+}
+
+/// The row `offer` should have in Baserow, given the row it has now.
+fn desired_row(offer: &Document, existing: Option<&Offers>) -> Offers {
+    let existing_status = existing
+        .and_then(|row| row.status.as_ref())
+        .map(ToString::to_string);
+    Offers {
+        easybill_id: Some(offer.id),
+        customer: Some(offer.customer_snapshot.company_name.clone()),
+        // easybill sends amounts in cents
+        amount: Some(offer.amount_net as f64 / 100.0),
+        status: OffersStatus::from_str(status(offer, existing_status.as_deref())).ok(),
+    }
+}
+
+/// Maps the state of `offer` in easybill to the value of the status field in Baserow.
+///
+/// Drafts are reviewed and approved in Baserow only, so these statuses are kept as long as
+/// the offer is a draft in easybill.
+fn status<'a>(offer: &Document, existing: Option<&'a str>) -> &'a str {
+    if offer.is_draft {
+        return match existing {
+            Some(status @ ("draft" | "review" | "approved")) => status,
+            _ => "draft",
+        };
+    }
+    match offer.status.as_deref() {
+        Some("ACCEPT" | "DONE") => "accepted",
+        Some("DROPPED" | "CANCEL") => "expired",
+        _ => "sent",
+    }
+}
+
+/// Compares the fields the sync writes, the color and ID of the status are set by Baserow.
+fn differs(existing: &Offers, desired: &Offers) -> bool {
+    existing.customer != desired.customer
+        || existing.amount != desired.amount
+        || existing.status.as_ref().map(ToString::to_string)
+            != desired.status.as_ref().map(ToString::to_string)
+}
+
+fn synced(offer: &Document, row: &Offers) -> SyncedOffer {
+    SyncedOffer {
+        easybill_id: offer.id,
+        customer: offer.customer_snapshot.company_name.clone(),
+        amount: row.amount.unwrap_or_default(),
+        status: row
+            .status
+            .as_ref()
+            .map(ToString::to_string)
+            .unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::baserow::client::Baserow;
+    use crate::baserow::config::BaserowConfig;
+    use crate::easybill::client::Easybill;
+    use crate::easybill::structs::Document;
+    use crate::offers::{status, sync_offers};
+    use serde_json::json;
+    use std::fs::read_to_string;
+    use wiremock::matchers::{body_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn fixture(file: &str) -> serde_json::Value {
+        serde_json::from_str(&read_to_string(file).unwrap()).unwrap()
+    }
+
+    /// Serves the easybill offers and the Baserow offers table, and expects `writes` rows to
+    /// be created and updated.
+    async fn mock_server(writes: u64) -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/rest/v1/documents"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(fixture("testdata/easybill/list_documents.json")),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/database/fields/table/568215/"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(fixture("testdata/baserow/offers_fields.json")),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/database/rows/table/568215/"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(fixture("testdata/list_offers1.json")),
+            )
+            .mount(&server)
+            .await;
+
+        let written = json!({
+            "id": 35,
+            "field_4565570": "124",
+            "field_4565571": "Company B",
+            "field_4565572": "4800.00",
+            "field_4565632": { "id": 3487584, "value": "sent", "color": "dark-cyan" }
+        });
+        Mock::given(method("POST"))
+            .and(path("/api/database/rows/table/568215/"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(&written))
+            .expect(2 * writes)
+            .mount(&server)
+            .await;
+        Mock::given(method("PATCH"))
+            .and(path("/api/database/rows/table/568215/34/"))
+            .and(body_json(json!({
+                "field_4565570": 123,
+                "field_4565571": "Company A",
+                "field_4565572": 12500.0,
+                "field_4565632": 3487586
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(&written))
+            .expect(writes)
+            .mount(&server)
+            .await;
+        server
+    }
+
+    fn clients(server: &MockServer) -> (Easybill, Baserow) {
+        (
+            Easybill::new(&format!("{}/rest/v1/", server.uri()), "api-key").unwrap(),
+            Baserow::new(BaserowConfig::new(&server.uri(), "database-token").unwrap()).unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_sync_offers() {
+        let server = mock_server(1).await;
+        let (easybill, baserow) = clients(&server);
+
+        let report = sync_offers(&easybill, &baserow, false).await.unwrap();
+
+        let created: Vec<(usize, &str)> = report
+            .created
+            .iter()
+            .map(|offer| (offer.easybill_id, offer.status.as_str()))
+            .collect();
+        assert_eq!(created, [(124, "sent"), (3210386482, "draft")]);
+        assert_eq!(report.updated.len(), 1);
+        assert_eq!(report.updated[0].easybill_id, 123);
+        assert_eq!(report.updated[0].status, "accepted");
+        assert_eq!(report.unchanged, 0);
+    }
+
+    #[tokio::test]
+    async fn test_sync_offers_dry_run() {
+        let server = mock_server(0).await;
+        let (easybill, baserow) = clients(&server);
+
+        let report = sync_offers(&easybill, &baserow, true).await.unwrap();
+
+        assert_eq!(report.created.len(), 2);
+        assert_eq!(report.updated.len(), 1);
+        assert!(report
+            .to_string()
+            .ends_with("(dry run, nothing has been written)"));
+    }
+
+    #[test]
+    fn test_status() {
+        let documents: Vec<Document> = serde_json::from_value(
+            fixture("testdata/easybill/list_documents.json")["items"].clone(),
+        )
+        .unwrap();
+        let (accepted, sent, draft) = (&documents[0], &documents[1], &documents[2]);
+
+        assert_eq!(status(accepted, Some("sent")), "accepted");
+        assert_eq!(status(sent, None), "sent");
+        assert_eq!(status(draft, None), "draft");
+        assert_eq!(status(draft, Some("review")), "review");
+        assert_eq!(status(draft, Some("sent")), "draft");
+    }
+}